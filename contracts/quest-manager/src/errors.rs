@@ -19,4 +19,30 @@ pub enum Error {
     NoWinners = 13,
     Unauthorized = 14,
     InsufficientBalance = 15,
+    InvalidRewardToken = 16,
+    QuestHasRegistrations = 17,
+    InvalidPrizeTable = 18,
+    InvalidDistribution = 19,
+    RewardsVesting = 20,
+    NothingToClaim = 21,
+    VestingNotConfigured = 22,
+    UserNotEligible = 23,
+    InvalidFee = 24,
+    AlreadyInitialized = 25,
+    NotInitialized = 26,
+    UserAlreadyEligible = 27,
+    InvalidQuestConfig = 28,
+    InvalidNonce = 29,
+    RegistrationClosed = 30,
+    NotAllowlisted = 31,
+    UserDenied = 32,
+    AccountTooNew = 33,
+    PrerequisiteNotMet = 34,
+    InvalidPrerequisite = 35,
+    TasksIncomplete = 36,
+    InvalidTask = 37,
+    InvalidReferrer = 38,
+    RewardsAlreadyDistributed = 39,
+    ArithmeticOverflow = 40,
+    LeaderboardFull = 41,
 }
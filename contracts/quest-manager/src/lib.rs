@@ -1,6 +1,7 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Env, Vec, Symbol, String, panic_with_error
+    contract, contractimpl, contracttype, token, Address, Bytes, BytesN, Env, IntoVal, Map, Vec, Symbol, String, panic_with_error
 };
 
 mod types;
@@ -20,6 +21,10 @@ pub use errors::*;
 pub enum DistributionType {
     Raffle, // Sorteio aleatório no final
     Fcfs,   // Primeiro a chegar, primeiro a ser servido
+    ProRata, // Pool dividido proporcionalmente à pontuação de cada elegível
+    WeightedRaffle, // Sorteio em que cada elegível tem chances proporcionais aos seus bilhetes
+    Leaderboard, // Os max_winners com maior pontuação ao final da quest ganham
+    Streaming, // Elegíveis dividem reward_per_winner por segundo enquanto permanecem elegíveis
 }
 
 // Enum para o tipo de tarefa que o usuário deve cumprir
@@ -29,6 +34,8 @@ pub enum QuestType {
     TradeVolume(u128), // Faça X de volume
     PoolPosition(u128), // Tenha pelo menos X em um pool
     TokenHold(Address, u128), // Holde X unidades do token Y
+    Referral(u32), // Indique X amigos que se tornem elegíveis depois do seu registro
+    Composite(Vec<QuestType>, u32), // Cumpra pelo menos N das sub-tarefas (N = total para AND, 1 para OR)
 }
 
 // Struct principal que define uma campanha/missão
@@ -60,6 +67,49 @@ pub enum DataKey {
     Registrations(u64, Address), // Usuário se registrou na Quest? (Quest ID, User Address) => bool
     QuestIds, // Lista de todos os IDs de Quest para iteração
     UserQuests(Address), // Quests em que um usuário específico está participando
+    Registrants(u64), // Lista de usuários registrados em uma Quest (ID => Vec<Address>)
+    Sponsors(u64), // Lista de patrocinadores que financiaram uma Quest (ID => Vec<Address>)
+    Contributions(u64, Address), // Total aportado por um patrocinador (Quest ID, Sponsor) => u128
+    BonusRewards(u64), // Tokens adicionais pagos por ganhador (ID => Vec<BonusReward>)
+    PrizeTable(u64), // Tabela de prêmios por posição (ID => Vec<PrizeTier>)
+    Scores(u64), // Pontuação reportada por usuário (ID => Map<Address, u128>)
+    Allocations(u64), // Prêmios calculados na resolução de quests ProRata (ID => Vec<WinnerPrize>)
+    Tickets(u64), // Bilhetes de cada participante no sorteio ponderado (ID => Map<Address, u32>)
+    Vesting(u64), // Cronograma de vesting das recompensas (ID => VestingSchedule)
+    VestingClaimed(u64, Address), // Quanto do reward_token um ganhador já resgatou (Quest ID, User) => u128
+    Stream(u64), // Acumulador de uma quest Streaming (ID => StreamState)
+    StreamPositions(u64, Address), // Posição de um usuário no streaming (Quest ID, User) => StreamPosition
+    EntryFee(u64), // Taxa de inscrição da Quest (ID => EntryFee)
+    ProtocolConfig, // Configuração do protocolo (ProtocolConfig)
+    FeeExempt(Address), // Criador isento da taxa do protocolo? (Creator => bool)
+    QuestFee(u64), // Taxa do protocolo aplicada a uma Quest (ID => QuestFee)
+    ProtocolFees(Address), // Taxas acumuladas por token, separadas dos pools (Token => u128)
+    OwedRewards(u64, Address), // Pagamentos pendentes de um ganhador (Quest ID, User) => Vec<OwedReward>
+    Config(u64), // Configurações avançadas da Quest (ID => QuestConfig)
+    Entries(u64, Address), // Entradas acumuladas por um usuário (Quest ID, User) => u32
+    Distributed(u64), // Recompensas da quest já distribuídas (ID => bool)
+    Nonce(Address), // Próximo nonce aceito em registros delegados (User => u64)
+    EntryFeePayer(u64, Address), // Relayer que pagou a inscrição de um usuário (Quest ID, User) => Address
+    Waitlist(u64), // Fila de espera quando max_participants foi atingido (ID => Vec<Address>)
+    RegistrationClosed(u64), // Registros fechados pelo admin (ID => bool)
+    Allowlist(u64), // Modo da allowlist da Quest, se houver (ID => AllowlistMode)
+    Allowed(u64, Address), // Usuário liberado pela allowlist (Quest ID, User) => bool
+    Compliance, // Endereço responsável pela denylist (Address)
+    Denied(Address), // Endereço bloqueado em todas as quests (User => bool)
+    FirstSeen(Address), // Primeira interação conhecida de um endereço (User => timestamp)
+    Registrar, // Endereço confiável que atesta a idade de contas (Address)
+    Prerequisites(u64), // Quests que precisam ser cumpridas antes do registro (ID => Vec<Prerequisite>)
+    Dependents(u64), // Quests que têm esta como pré-requisito (ID => Vec<u64>)
+    TaskCompletions(u64, Address), // Sub-tarefas concluídas por um usuário (Quest ID, User) => Vec<bool>
+    Progress(u64, Address), // Progresso reportado em cada tarefa (Quest ID, User) => Vec<u128>
+    Referrer(Address), // Quem indicou o usuário (User => Referral)
+    Referrals(Address), // Indicados que se tornaram elegíveis (Referrer => u32)
+    Badges(Address), // Badges intransferíveis do usuário (User => Vec<Badge>)
+    UserStats(Address), // Estatísticas e XP acumulados do usuário (User => UserStats)
+    SeasonXp(u32, Address), // XP do usuário em uma temporada (Season, User) => u128
+    Season, // Temporada de XP atual
+    TopUsers(u32), // Usuários com mais XP na temporada (Season => Vec<LeaderboardEntry>)
+    RegistrationXp(u64, Address), // XP de registro já concedido (Quest ID, User) => bool
 }
 
 // Struct para eventos
@@ -79,6 +129,14 @@ pub struct UserRegisteredEvent {
     pub user: Address,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UserUnregisteredEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub refund: u128,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct QuestResolvedEvent {
@@ -86,6 +144,170 @@ pub struct QuestResolvedEvent {
     pub winners_count: u32,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuestUpdatedEvent {
+    pub quest_id: u64,
+    pub changed_fields: Vec<Symbol>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuestFundedEvent {
+    pub quest_id: u64,
+    pub sponsor: Address,
+    pub amount: u128,
+    pub total_reward_pool: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BonusRewardAddedEvent {
+    pub quest_id: u64,
+    pub token: Address,
+    pub amount_per_winner: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrizeTableSetEvent {
+    pub quest_id: u64,
+    pub max_winners: u32,
+    pub total_prizes: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScoreSubmittedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub score: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TicketsAssignedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub tickets: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VestedClaimedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub amount: u128,
+    pub total_claimed: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamClaimedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub amount: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntryFeePaidEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub amount: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolFeeChargedEvent {
+    pub quest_id: u64,
+    pub token: Address,
+    pub amount: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolFeeUpdatedEvent {
+    pub fee_bps: u32,
+    pub fee_base: FeeBase,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolFeesWithdrawnEvent {
+    pub token: Address,
+    pub treasury: Address,
+    pub amount: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayoutFailedEvent {
+    pub quest_id: u64,
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: u128,
+    pub reason: PayoutFailureReason,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OwedClaimedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub token: Address,
+    pub amount: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DenylistUpdatedEvent {
+    pub user: Address,
+    pub denied: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskCompletedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub task_index: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProgressUpdatedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub task_index: u32,
+    pub current: u128,
+    pub target: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeasonStartedEvent {
+    pub season: u32,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BadgeMintedEvent {
+    pub quest_id: u64,
+    pub user: Address,
+    pub kind: BadgeKind,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferralCreditedEvent {
+    pub quest_id: u64,
+    pub referrer: Address,
+    pub user: Address,
+    pub bonus: u128,
+}
+
 #[contract]
 pub struct QuestManager;
 
@@ -115,35 +337,57 @@ impl QuestManager {
         if reward_per_winner == 0 {
             panic_with_error!(&env, Error::InvalidRewardAmount);
         }
-        if reward_pool_amount < (reward_per_winner * max_winners as u128) {
+        // Em ProRata, reward_per_winner é o limite por usuário e o pool é dividido entre os elegíveis
+        // Em Streaming, reward_per_winner é a emissão por segundo durante toda a quest
+        let required_pool = match distribution {
+            DistributionType::ProRata => Some(reward_per_winner),
+            DistributionType::Streaming => reward_per_winner.checked_mul(duration_seconds as u128),
+            _ => reward_per_winner.checked_mul(max_winners as u128),
+        }.unwrap_or_else(|| panic_with_error!(&env, Error::InvalidRewardAmount));
+        if reward_pool_amount < required_pool {
             panic_with_error!(&env, Error::InsufficientRewardPool);
         }
         if duration_seconds == 0 {
             panic_with_error!(&env, Error::InvalidDuration);
         }
-        
-        // Cria o cliente do token de recompensa
-        let reward_token_client = token::Client::new(&env, &reward_token);
-        
-        //Verifica se usuario tem saldo para pagar a reward pool
-        if reward_pool_amount > 0  {
-            let balance = reward_token_client.balance(&admin);
-            if balance < (reward_pool_amount as i128) {
-                panic_with_error!(&env, Error::InsufficientBalance); 
+        // Sub-tarefas são tarefas simples e o mínimo exigido precisa ser alcançável
+        if let QuestType::Composite(tasks, required) = &quest_type {
+            if *required == 0
+                || *required > tasks.len()
+                || tasks.iter().any(|task| matches!(task, QuestType::Composite(..)))
+            {
+                panic_with_error!(&env, Error::InvalidQuestConfig);
             }
-            
-            // Transfere os tokens de recompensa para o contrato
-            reward_token_client.transfer(
-                &admin,
-                &env.current_contract_address(),
-                &(reward_pool_amount as i128)
-            );
         }
-
+        // Tarefas Referral tornam o indicador elegível automaticamente, o que o Leaderboard não aceita
+        if distribution == DistributionType::Leaderboard && StorageHelper::has_referral_task(&quest_type) {
+            panic_with_error!(&env, Error::InvalidQuestConfig);
+        }
+        
         // Gera um novo ID para a quest
         let quest_id: u64 = env.storage().instance().get(&DataKey::QuestCounter).unwrap_or(0);
         env.storage().instance().set(&DataKey::QuestCounter, &(quest_id + 1));
 
+        // Registra a taxa do protocolo vigente, a menos que o criador seja isento
+        let protocol_config: Option<ProtocolConfig> = env.storage().instance().get(&DataKey::ProtocolConfig);
+        if let Some(config) = protocol_config {
+            let is_exempt: bool = env.storage().persistent()
+                .get(&DataKey::FeeExempt(admin.clone()))
+                .unwrap_or(false);
+            if config.fee_bps > 0 && !is_exempt {
+                env.storage().persistent().set(&DataKey::QuestFee(quest_id), &QuestFee {
+                    fee_bps: config.fee_bps,
+                    fee_base: config.fee_base,
+                });
+            }
+        }
+
+        // Transfere os tokens de recompensa do admin para o contrato
+        if reward_pool_amount > 0  {
+            Self::collect_funds(&env, &reward_token, &admin, reward_pool_amount);
+            Self::charge_pool_fee(&env, quest_id, &reward_token, &admin, reward_pool_amount);
+        }
+
         let new_quest = Quest {
             id: quest_id,
             admin: admin.clone(),
@@ -171,6 +415,23 @@ impl QuestManager {
         let empty_vec: Vec<Address> = Vec::new(&env);
         env.storage().persistent().set(&DataKey::Participants(quest_id), &empty_vec);
         env.storage().persistent().set(&DataKey::Winners(quest_id), &empty_vec);
+        env.storage().persistent().set(&DataKey::Registrants(quest_id), &empty_vec);
+
+        if distribution == DistributionType::Streaming {
+            env.storage().persistent().set(&DataKey::Stream(quest_id), &StreamState {
+                start_timestamp: env.ledger().timestamp(),
+                last_update: env.ledger().timestamp(),
+                acc_reward_per_user: 0,
+                eligible_count: 0,
+                distributed: 0,
+                undistributed: 0,
+            });
+        }
+
+        // O aporte inicial do admin conta como a primeira contribuição
+        if reward_pool_amount > 0 {
+            StorageHelper::add_contribution(&env, quest_id, &admin, reward_pool_amount);
+        }
 
         // Emit event
         env.events().publish((Symbol::new(&env, "quest_created"),), QuestCreatedEvent {
@@ -183,105 +444,935 @@ impl QuestManager {
         quest_id // Retorna o ID da nova quest
     }
 
-    /// Permite que um usuário se registre para participar de uma quest
-    pub fn register(env: Env, quest_id: u64, user: Address) {
-        user.require_auth(); // Garante que o usuário está assinando a transação
-
-        // Verifica se a quest existe e está ativa
-        let quest: Quest = env.storage().persistent()
+    /// Atualiza prazo, textos, número de ganhadores ou recompensa de uma quest ainda não resolvida
+    /// Após o primeiro registro, o prazo, max_winners e reward_per_winner só podem aumentar
+    pub fn update_quest(env: Env, quest_id: u64, update: QuestUpdate) {
+        let mut quest: Quest = env.storage().persistent()
             .get(&DataKey::Quests(quest_id))
             .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
-        
+
+        quest.admin.require_auth();
+
         if !quest.is_active {
             panic_with_error!(&env, Error::QuestNotActive);
         }
-        
-        if env.ledger().timestamp() > quest.end_timestamp {
-            panic_with_error!(&env, Error::QuestExpired);
+
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        let has_registrations = !registrants.is_empty();
+
+        // Em quests com tabela de prêmios, ganhadores e valores são definidos pela tabela
+        let has_prize_table = env.storage().persistent().has(&DataKey::PrizeTable(quest_id));
+        if has_prize_table && (update.max_winners.is_some() || update.reward_per_winner.is_some()) {
+            panic_with_error!(&env, Error::InvalidPrizeTable);
         }
 
-        // Verifica se o usuário já está registrado
-        if env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
-            panic_with_error!(&env, Error::AlreadyRegistered);
+        let mut changed_fields: Vec<Symbol> = Vec::new(&env);
+
+        if let Some(end_timestamp) = update.end_timestamp {
+            if end_timestamp <= env.ledger().timestamp() {
+                panic_with_error!(&env, Error::InvalidDuration);
+            }
+            // Não permite encurtar o prazo depois que usuários se registraram
+            if has_registrations && end_timestamp < quest.end_timestamp {
+                panic_with_error!(&env, Error::InvalidDuration);
+            }
+            if end_timestamp != quest.end_timestamp {
+                quest.end_timestamp = end_timestamp;
+                changed_fields.push_back(Symbol::new(&env, "end_timestamp"));
+            }
         }
 
-        // Registra o usuário
-        env.storage().persistent().set(&DataKey::Registrations(quest_id, user.clone()), &true);
+        if let Some(title) = update.title {
+            if title != quest.title {
+                quest.title = title;
+                changed_fields.push_back(Symbol::new(&env, "title"));
+            }
+        }
 
-        // Atualiza a lista de quests do usuário
-        let mut user_quests: Vec<u64> = env.storage().persistent()
-            .get(&DataKey::UserQuests(user.clone()))
-            .unwrap_or(Vec::new(&env));
-        user_quests.push_back(quest_id);
-        env.storage().persistent().set(&DataKey::UserQuests(user.clone()), &user_quests);
+        if let Some(description) = update.description {
+            if description != quest.description {
+                quest.description = description;
+                changed_fields.push_back(Symbol::new(&env, "description"));
+            }
+        }
+
+        if let Some(max_winners) = update.max_winners {
+            if max_winners == 0 {
+                panic_with_error!(&env, Error::InvalidMaxWinners);
+            }
+            if has_registrations && max_winners < quest.max_winners {
+                panic_with_error!(&env, Error::InvalidMaxWinners);
+            }
+            if max_winners != quest.max_winners {
+                quest.max_winners = max_winners;
+                changed_fields.push_back(Symbol::new(&env, "max_winners"));
+            }
+        }
+
+        if let Some(reward_per_winner) = update.reward_per_winner {
+            if reward_per_winner == 0 {
+                panic_with_error!(&env, Error::InvalidRewardAmount);
+            }
+            if has_registrations && reward_per_winner < quest.reward_per_winner {
+                panic_with_error!(&env, Error::InvalidRewardAmount);
+            }
+            if reward_per_winner != quest.reward_per_winner {
+                // No streaming, o que foi emitido até agora usa a taxa antiga
+                if quest.distribution == DistributionType::Streaming {
+                    StorageHelper::update_stream(&env, &quest);
+                }
+                quest.reward_per_winner = reward_per_winner;
+                changed_fields.push_back(Symbol::new(&env, "reward_per_winner"));
+            }
+        }
+
+        // Se o pool atual não cobre mais todos os ganhadores, o admin deposita a diferença
+        let required_pool = StorageHelper::required_reward_pool(&env, &quest);
+        if required_pool > quest.total_reward_pool {
+            let additional_funding = required_pool - quest.total_reward_pool;
+            Self::collect_funds(&env, &quest.reward_token, &quest.admin, additional_funding);
+            Self::charge_pool_fee(&env, quest_id, &quest.reward_token, &quest.admin, additional_funding);
+            StorageHelper::add_contribution(&env, quest_id, &quest.admin, additional_funding);
+
+            quest.total_reward_pool = required_pool;
+            changed_fields.push_back(Symbol::new(&env, "total_reward_pool"));
+        }
+
+        // Os tokens adicionais também precisam cobrir todos os ganhadores
+        let mut bonus_rewards = StorageHelper::get_bonus_rewards(&env, quest_id);
+        let mut bonus_topped_up = false;
+        for index in 0..bonus_rewards.len() {
+            let mut bonus = bonus_rewards.get(index).unwrap();
+            let required_bonus_pool = bonus.amount_per_winner * quest.max_winners as u128;
+            if required_bonus_pool > bonus.total_pool {
+                Self::collect_funds(&env, &bonus.token, &quest.admin, required_bonus_pool - bonus.total_pool);
+                bonus.total_pool = required_bonus_pool;
+                bonus_rewards.set(index, bonus);
+                bonus_topped_up = true;
+            }
+        }
+        if bonus_topped_up {
+            env.storage().persistent().set(&DataKey::BonusRewards(quest_id), &bonus_rewards);
+            changed_fields.push_back(Symbol::new(&env, "bonus_rewards"));
+        }
+
+        env.storage().persistent().set(&DataKey::Quests(quest_id), &quest);
 
         // Emit event
-        env.events().publish((Symbol::new(&env, "user_registered"),), UserRegisteredEvent {
+        env.events().publish((Symbol::new(&env, "quest_updated"),), QuestUpdatedEvent {
             quest_id,
-            user,
+            changed_fields,
         });
     }
 
-    /// Marca um usuário como elegível (chamado pelo backend quando o usuário completa a tarefa)
-    pub fn mark_user_eligible(env: Env, quest_id: u64, user: Address) {
-        // Verifica se a quest existe
+    /// Permite que qualquer patrocinador adicione fundos ao pool de uma quest
+    /// O modo define se o aporte aumenta max_winners, reward_per_winner ou apenas o pool
+    pub fn fund_quest(env: Env, quest_id: u64, from: Address, amount: u128, mode: FundingMode) {
+        from.require_auth();
+
+        let mut quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if amount == 0 {
+            panic_with_error!(&env, Error::InvalidRewardAmount);
+        }
+        StorageHelper::validate_funding_mode(&env, &quest, mode);
+
+        Self::collect_funds(&env, &quest.reward_token, &from, amount);
+        Self::charge_pool_fee(&env, quest_id, &quest.reward_token, &from, amount);
+        StorageHelper::add_contribution(&env, quest_id, &from, amount);
+        StorageHelper::apply_funding(&env, &mut quest, amount, mode);
+
+        env.storage().persistent().set(&DataKey::Quests(quest_id), &quest);
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "quest_funded"),), QuestFundedEvent {
+            quest_id,
+            sponsor: from,
+            amount,
+            total_reward_pool: quest.total_reward_pool,
+        });
+    }
+
+    /// Adiciona um token extra ao pacote de recompensas de uma quest
+    /// O admin deposita amount_per_winner * max_winners do token em custódia
+    pub fn add_bonus_reward(env: Env, quest_id: u64, token: Address, amount_per_winner: u128) {
         let quest: Quest = env.storage().persistent()
             .get(&DataKey::Quests(quest_id))
             .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
-        
-        // Apenas o admin da quest pode chamar esta função (nosso back-end)
+
         quest.admin.require_auth();
 
         if !quest.is_active {
             panic_with_error!(&env, Error::QuestNotActive);
         }
-
-        // Verifica se o usuário se registrou
-        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
-            panic_with_error!(&env, Error::UserNotRegistered);
+        if amount_per_winner == 0 {
+            panic_with_error!(&env, Error::InvalidRewardAmount);
+        }
+        // Quests Streaming só pagam o token principal
+        if quest.distribution == DistributionType::Streaming {
+            panic_with_error!(&env, Error::InvalidDistribution);
         }
 
-        // Lógica de adição baseada no tipo de distribuição
-        match quest.distribution {
-            DistributionType::Fcfs => {
-                let mut winners: Vec<Address> = env.storage().persistent()
-                    .get(&DataKey::Winners(quest_id))
-                    .unwrap_or(Vec::new(&env));
-                
-                if winners.len() < quest.max_winners {
-                    if !winners.contains(&user) { // Evita adicionar o mesmo ganhador duas vezes
-                        winners.push_back(user);
-                        env.storage().persistent().set(&DataKey::Winners(quest_id), &winners);
-                    }
-                }
-            },
-            DistributionType::Raffle => {
-                let mut participants: Vec<Address> = env.storage().persistent()
-                    .get(&DataKey::Participants(quest_id))
-                    .unwrap_or(Vec::new(&env));
-                
-                if !participants.contains(&user) { // Evita adicionar o mesmo participante duas vezes
-                    participants.push_back(user);
-                    env.storage().persistent().set(&DataKey::Participants(quest_id), &participants);
-                }
-            }
+        let mut bonus_rewards = StorageHelper::get_bonus_rewards(&env, quest_id);
+
+        // Cada token só pode aparecer uma vez no pacote
+        if token == quest.reward_token || bonus_rewards.iter().any(|bonus| bonus.token == token) {
+            panic_with_error!(&env, Error::InvalidRewardToken);
         }
+
+        let total_pool = amount_per_winner * quest.max_winners as u128;
+        Self::collect_funds(&env, &token, &quest.admin, total_pool);
+
+        bonus_rewards.push_back(BonusReward {
+            token: token.clone(),
+            amount_per_winner,
+            total_pool,
+        });
+        env.storage().persistent().set(&DataKey::BonusRewards(quest_id), &bonus_rewards);
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "bonus_reward_added"),), BonusRewardAddedEvent {
+            quest_id,
+            token,
+            amount_per_winner,
+        });
     }
 
-    /// Resolve uma quest (faz o sorteio se necessário)
-    pub fn resolve_quest(env: Env, quest_id: u64) {
-        let quest: Quest = env.storage().persistent()
+    /// Define uma tabela de prêmios por posição (ex: 1º: 5000, 2º–5º: 1000, 6º–50º: 100)
+    /// Só pode ser definida antes do primeiro registro e o pool precisa cobrir a tabela inteira
+    pub fn set_prize_table(env: Env, quest_id: u64, tiers: Vec<PrizeTier>) {
+        let mut quest: Quest = env.storage().persistent()
             .get(&DataKey::Quests(quest_id))
             .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
-        
+
         quest.admin.require_auth();
 
-        if env.ledger().timestamp() < quest.end_timestamp {
-            panic_with_error!(&env, Error::QuestNotFinished);
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        // Em ProRata os valores vêm das pontuações e em Streaming da emissão por segundo
+        if matches!(quest.distribution, DistributionType::ProRata | DistributionType::Streaming) {
+            panic_with_error!(&env, Error::InvalidDistribution);
         }
 
-        if !quest.is_active {
-            panic_with_error!(&env, Error::QuestAlreadyResolved);
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if !registrants.is_empty() {
+            panic_with_error!(&env, Error::QuestHasRegistrations);
+        }
+
+        if tiers.is_empty() {
+            panic_with_error!(&env, Error::InvalidPrizeTable);
+        }
+
+        // As faixas devem ser crescentes e ter valores positivos
+        let mut previous_rank = 0u32;
+        let mut total_prizes = 0u128;
+        for tier in tiers.iter() {
+            if tier.last_rank <= previous_rank || tier.amount == 0 {
+                panic_with_error!(&env, Error::InvalidPrizeTable);
+            }
+            total_prizes += (tier.last_rank - previous_rank) as u128 * tier.amount;
+            previous_rank = tier.last_rank;
+        }
+
+        if total_prizes > quest.total_reward_pool {
+            panic_with_error!(&env, Error::InsufficientRewardPool);
+        }
+
+        // Os tokens adicionais precisam cobrir o novo número de ganhadores
+        for bonus in StorageHelper::get_bonus_rewards(&env, quest_id).iter() {
+            if bonus.amount_per_winner * previous_rank as u128 > bonus.total_pool {
+                panic_with_error!(&env, Error::InsufficientRewardPool);
+            }
+        }
+
+        quest.max_winners = previous_rank;
+        env.storage().persistent().set(&DataKey::Quests(quest_id), &quest);
+        env.storage().persistent().set(&DataKey::PrizeTable(quest_id), &tiers);
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "prize_table_set"),), PrizeTableSetEvent {
+            quest_id,
+            max_winners: previous_rank,
+            total_prizes,
+        });
+    }
+
+    /// Define um vesting para as recompensas da quest (cliff + liberação linear a partir da resolução)
+    /// Com vesting, os ganhadores resgatam via claim_vested em vez de distribute_rewards
+    pub fn set_vesting(env: Env, quest_id: u64, cliff_seconds: u64, duration_seconds: u64) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if !registrants.is_empty() {
+            panic_with_error!(&env, Error::QuestHasRegistrations);
+        }
+
+        if duration_seconds == 0 || cliff_seconds > duration_seconds {
+            panic_with_error!(&env, Error::InvalidDuration);
+        }
+
+        env.storage().persistent().set(&DataKey::Vesting(quest_id), &VestingSchedule {
+            cliff_seconds,
+            duration_seconds,
+            start_timestamp: None,
+        });
+    }
+
+    /// Define as configurações avançadas da quest, antes do primeiro registro
+    /// Múltiplas entradas só valem para Fcfs (uma vaga por conclusão) e Raffle (um bilhete por conclusão)
+    pub fn set_quest_config(env: Env, quest_id: u64, config: QuestConfig) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if !registrants.is_empty() {
+            panic_with_error!(&env, Error::QuestHasRegistrations);
+        }
+
+        if config.allow_multiple_entries {
+            if config.max_entries_per_user == 0 {
+                panic_with_error!(&env, Error::InvalidQuestConfig);
+            }
+            if quest.distribution != DistributionType::Fcfs && quest.distribution != DistributionType::Raffle {
+                panic_with_error!(&env, Error::InvalidDistribution);
+            }
+        }
+
+        // O XP é limitado pelo protocolo para que o ranking de temporada não seja inflado por uma quest
+        if config.xp.registration > MAX_XP_REWARD || config.xp.completion > MAX_XP_REWARD || config.xp.win > MAX_XP_REWARD {
+            panic_with_error!(&env, Error::InvalidQuestConfig);
+        }
+
+        env.storage().persistent().set(&DataKey::Config(quest_id), &config);
+    }
+
+    /// Adiciona ou remove endereços da allowlist explícita de uma quest
+    /// A primeira chamada restringe a quest aos endereços liberados
+    pub fn set_allowlist(env: Env, quest_id: u64, users: Vec<Address>, allowed: bool) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        if !env.storage().persistent().has(&DataKey::Allowlist(quest_id)) {
+            env.storage().persistent().set(&DataKey::Allowlist(quest_id), &AllowlistMode::Explicit);
+        }
+
+        for user in users.iter() {
+            let key = DataKey::Allowed(quest_id, user);
+            if allowed {
+                env.storage().persistent().set(&key, &true);
+            } else {
+                env.storage().persistent().remove(&key);
+            }
+        }
+    }
+
+    /// Restringe a quest aos endereços de uma árvore de Merkle (ex: holders de uma campanha anterior)
+    /// Endereços liberados explicitamente continuam aceitos
+    pub fn set_allowlist_root(env: Env, quest_id: u64, root: BytesN<32>) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        env.storage().persistent().set(&DataKey::Allowlist(quest_id), &AllowlistMode::Merkle(root));
+    }
+
+    /// Define as quests que o usuário precisa ter cumprido antes de se registrar, antes do primeiro registro
+    /// Só quests anteriores podem ser pré-requisito, o que impede ciclos na cadeia
+    pub fn set_prerequisites(env: Env, quest_id: u64, prerequisites: Vec<Prerequisite>) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if !registrants.is_empty() {
+            panic_with_error!(&env, Error::QuestHasRegistrations);
+        }
+
+        for (index, prerequisite) in prerequisites.iter().enumerate() {
+            if prerequisite.quest_id >= quest_id
+                || !env.storage().persistent().has(&DataKey::Quests(prerequisite.quest_id))
+                || prerequisites.iter().skip(index + 1).any(|other| other.quest_id == prerequisite.quest_id)
+            {
+                panic_with_error!(&env, Error::InvalidPrerequisite);
+            }
+        }
+
+        // Atualiza o índice reverso usado para percorrer a cadeia adiante
+        for previous in StorageHelper::get_prerequisites(&env, quest_id).iter() {
+            let mut dependents: Vec<u64> = env.storage().persistent()
+                .get(&DataKey::Dependents(previous.quest_id))
+                .unwrap_or(Vec::new(&env));
+            if let Some(index) = dependents.first_index_of(quest_id) {
+                dependents.remove(index);
+                env.storage().persistent().set(&DataKey::Dependents(previous.quest_id), &dependents);
+            }
+        }
+        for prerequisite in prerequisites.iter() {
+            let mut dependents: Vec<u64> = env.storage().persistent()
+                .get(&DataKey::Dependents(prerequisite.quest_id))
+                .unwrap_or(Vec::new(&env));
+            dependents.push_back(quest_id);
+            env.storage().persistent().set(&DataKey::Dependents(prerequisite.quest_id), &dependents);
+        }
+
+        env.storage().persistent().set(&DataKey::Prerequisites(quest_id), &prerequisites);
+    }
+
+    /// Abre ou fecha os registros (e a fila de espera) de uma quest
+    pub fn set_registration_open(env: Env, quest_id: u64, open: bool) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        if open {
+            env.storage().persistent().remove(&DataKey::RegistrationClosed(quest_id));
+        } else {
+            env.storage().persistent().set(&DataKey::RegistrationClosed(quest_id), &true);
+        }
+    }
+
+    /// Define uma taxa de inscrição, cobrada no reward_token e somada ao pool de prêmios
+    /// admin_cut_bps fica com o admin na resolução; em caso de cancelamento tudo é devolvido
+    pub fn set_entry_fee(env: Env, quest_id: u64, amount: u128, admin_cut_bps: u32, mode: FundingMode) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if !registrants.is_empty() {
+            panic_with_error!(&env, Error::QuestHasRegistrations);
+        }
+
+        if amount == 0 || admin_cut_bps > 10_000 {
+            panic_with_error!(&env, Error::InvalidFee);
+        }
+        StorageHelper::validate_funding_mode(&env, &quest, mode);
+
+        env.storage().persistent().set(&DataKey::EntryFee(quest_id), &EntryFee {
+            amount,
+            admin_cut_bps,
+            mode,
+            collected_cut: 0,
+        });
+    }
+
+    /// Permite que um usuário se registre para participar de uma quest
+    pub fn register(env: Env, quest_id: u64, user: Address) {
+        user.require_auth(); // Garante que o usuário está assinando a transação
+
+        Self::register_user(&env, quest_id, &user, &user);
+    }
+
+    /// Registra um usuário através de um relayer, que envia a transação e paga as taxas
+    /// O usuário só assina (quest_id, nonce), via entrada de autorização pré-assinada,
+    /// e a taxa de inscrição, se houver, é paga pelo relayer
+    pub fn register_for(env: Env, quest_id: u64, user: Address, relayer: Address, nonce: u64) {
+        relayer.require_auth();
+        user.require_auth_for_args((quest_id, nonce).into_val(&env));
+
+        // O nonce sequencial impede que a mesma assinatura seja reutilizada
+        let expected_nonce: u64 = env.storage().persistent()
+            .get(&DataKey::Nonce(user.clone()))
+            .unwrap_or(0);
+        if nonce != expected_nonce {
+            panic_with_error!(&env, Error::InvalidNonce);
+        }
+        env.storage().persistent().set(&DataKey::Nonce(user.clone()), &(nonce + 1));
+
+        Self::register_user(&env, quest_id, &user, &relayer);
+    }
+
+    /// Registra um usuário indicado por outro endereço
+    /// A indicação é gravada só na primeira vez; registros posteriores mantêm o indicador original
+    pub fn register_with_referrer(env: Env, quest_id: u64, user: Address, referrer: Address) {
+        user.require_auth();
+
+        if referrer == user {
+            panic_with_error!(&env, Error::InvalidReferrer);
+        }
+
+        let key = DataKey::Referrer(user.clone());
+        if !env.storage().persistent().has(&key) {
+            env.storage().persistent().set(&key, &Referral { referrer, credited: false });
+        }
+
+        Self::register_user(&env, quest_id, &user, &user);
+    }
+
+    /// Registra um usuário em uma quest com allowlist por Merkle, apresentando a prova de inclusão
+    pub fn register_with_proof(env: Env, quest_id: u64, user: Address, proof: Vec<BytesN<32>>) {
+        user.require_auth();
+
+        let mode: Option<AllowlistMode> = env.storage().persistent().get(&DataKey::Allowlist(quest_id));
+        let Some(AllowlistMode::Merkle(root)) = mode else {
+            panic_with_error!(&env, Error::InvalidQuestConfig);
+        };
+        if !StorageHelper::verify_merkle_proof(&env, &root, &user, &proof) {
+            panic_with_error!(&env, Error::NotAllowlisted);
+        }
+        env.storage().persistent().set(&DataKey::Allowed(quest_id, user.clone()), &true);
+
+        Self::register_user(&env, quest_id, &user, &user);
+    }
+
+    /// Cancela o registro de um usuário (ou o tira da fila de espera), antes de ele se tornar elegível
+    /// e antes do fim da quest. A taxa de inscrição paga é devolvida integralmente
+    pub fn unregister(env: Env, quest_id: u64, user: Address) {
+        user.require_auth();
+
+        let mut quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if env.ledger().timestamp() > quest.end_timestamp {
+            panic_with_error!(&env, Error::QuestExpired);
+        }
+        // Quem está na fila de espera apenas sai dela
+        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
+            let refund = Self::leave_waitlist(&env, &quest, &user)
+                .unwrap_or_else(|| panic_with_error!(&env, Error::UserNotRegistered));
+
+            // Emit event
+            env.events().publish((Symbol::new(&env, "user_unregistered"),), UserUnregisteredEvent {
+                quest_id,
+                user,
+                refund,
+            });
+            return;
+        }
+        if StorageHelper::has_qualified(&env, &quest, &user) {
+            panic_with_error!(&env, Error::UserAlreadyEligible);
+        }
+
+        // Remove o registro e as listas que o referenciam
+        env.storage().persistent().remove(&DataKey::Registrations(quest_id, user.clone()));
+        env.storage().persistent().remove(&DataKey::TaskCompletions(quest_id, user.clone()));
+        env.storage().persistent().remove(&DataKey::Progress(quest_id, user.clone()));
+
+        let mut user_quests: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::UserQuests(user.clone()))
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = user_quests.first_index_of(quest_id) {
+            user_quests.remove(index);
+        }
+        if user_quests.is_empty() {
+            env.storage().persistent().remove(&DataKey::UserQuests(user.clone()));
+        } else {
+            env.storage().persistent().set(&DataKey::UserQuests(user.clone()), &user_quests);
+        }
+
+        let mut registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = registrants.first_index_of(&user) {
+            registrants.remove(index);
+            env.storage().persistent().set(&DataKey::Registrants(quest_id), &registrants);
+        }
+
+        // A participação é estornada; o XP de registro não, já que só é concedido uma vez por quest
+        // Registros anteriores ao ledger de estatísticas não foram contados, daí a subtração saturada
+        let mut stats = StorageHelper::get_user_stats(&env, &user);
+        stats.total_participated = stats.total_participated.saturating_sub(1);
+        StorageHelper::set_user_stats(&env, &user, stats);
+
+        // Devolve a taxa de inscrição a quem pagou: a parte do admin sai da custódia e a do pool é retirada do pool
+        let mut refund = 0u128;
+        let entry_fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest_id));
+        if let Some(mut fee) = entry_fee {
+            let payer = StorageHelper::get_entry_fee_payer(&env, quest_id, &user);
+            env.storage().persistent().remove(&DataKey::EntryFeePayer(quest_id, user.clone()));
+
+            let cut = fee.amount * fee.admin_cut_bps as u128 / 10_000;
+            let pooled = fee.amount - cut;
+
+            fee.collected_cut -= cut;
+            env.storage().persistent().set(&DataKey::EntryFee(quest_id), &fee);
+
+            if pooled > 0 {
+                StorageHelper::remove_contribution(&env, quest_id, &payer, pooled);
+                StorageHelper::revert_funding(&env, &mut quest, pooled, fee.mode);
+                env.storage().persistent().set(&DataKey::Quests(quest_id), &quest);
+            }
+
+            // Se o pagador não puder receber, a devolução fica devida e resgatável via claim_owed
+            refund = fee.amount;
+            Self::pay_or_owe(&env, quest_id, &quest.reward_token, &payer, refund);
+        }
+
+        // A vaga liberada vai para o próximo da fila de espera
+        Self::promote_from_waitlist(&env, quest_id);
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "user_unregistered"),), UserUnregisteredEvent {
+            quest_id,
+            user,
+            refund,
+        });
+    }
+
+    /// Marca um usuário como elegível (chamado pelo backend quando o usuário completa a tarefa)
+    pub fn mark_user_eligible(env: Env, quest_id: u64, user: Address) {
+        // Verifica se a quest existe
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+        
+        // Apenas o admin da quest pode chamar esta função (nosso back-end)
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+
+        // Verifica se o usuário se registrou
+        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
+            panic_with_error!(&env, Error::UserNotRegistered);
+        }
+        StorageHelper::require_not_denied(&env, &user);
+
+        // Em quests compostas, a elegibilidade depende das sub-tarefas concluídas
+        if !StorageHelper::has_completed_tasks(&env, &quest, &user) {
+            panic_with_error!(&env, Error::TasksIncomplete);
+        }
+
+        Self::make_eligible(&env, &quest, &user);
+    }
+
+    /// Marca uma sub-tarefa de uma quest composta como concluída pelo usuário (chamado pelo backend)
+    /// O usuário se torna elegível automaticamente quando atinge o mínimo de sub-tarefas
+    pub fn complete_task(env: Env, quest_id: u64, user: Address, task_index: u32) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
+            panic_with_error!(&env, Error::UserNotRegistered);
+        }
+        StorageHelper::require_not_denied(&env, &user);
+
+        let QuestType::Composite(tasks, _) = &quest.quest_type else {
+            panic_with_error!(&env, Error::InvalidQuestConfig);
+        };
+        if task_index >= tasks.len() {
+            panic_with_error!(&env, Error::InvalidTask);
+        }
+
+        Self::record_task_completion(&env, &quest, &user, task_index);
+    }
+
+    /// Reporta o progresso atual de um usuário em uma tarefa (ex: volume negociado até agora)
+    /// Pode ser chamado pelo admin ou pelo adaptador on-chain configurado na quest
+    /// Em quests simples task_index é 0; ao atingir a meta, a tarefa é concluída automaticamente
+    pub fn report_progress(env: Env, quest_id: u64, user: Address, reporter: Address, task_index: u32, value: u128) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        reporter.require_auth();
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        let adapter = config.and_then(|config| config.progress_adapter);
+        if reporter != quest.admin && Some(reporter) != adapter {
+            panic_with_error!(&env, Error::Unauthorized);
+        }
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
+            panic_with_error!(&env, Error::UserNotRegistered);
+        }
+        StorageHelper::require_not_denied(&env, &user);
+
+        if task_index >= StorageHelper::get_tasks(&env, &quest).len() {
+            panic_with_error!(&env, Error::InvalidTask);
+        }
+
+        Self::update_task_progress(&env, &quest, &user, task_index, value);
+    }
+
+    /// Marca um usuário como não elegível em uma quest Streaming, interrompendo seu acúmulo
+    /// O que já foi acumulado continua disponível em claim_stream
+    pub fn mark_user_ineligible(env: Env, quest_id: u64, user: Address) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if quest.distribution != DistributionType::Streaming {
+            panic_with_error!(&env, Error::InvalidDistribution);
+        }
+
+        let mut state = StorageHelper::update_stream(&env, &quest);
+        let mut position = StorageHelper::get_stream_position(&env, quest_id, &user);
+
+        if !position.is_eligible {
+            panic_with_error!(&env, Error::UserNotEligible);
+        }
+
+        position.pending += (state.acc_reward_per_user - position.acc_snapshot) / STREAM_PRECISION;
+        position.acc_snapshot = state.acc_reward_per_user;
+        position.is_eligible = false;
+        state.eligible_count -= 1;
+
+        env.storage().persistent().set(&DataKey::Stream(quest_id), &state);
+        env.storage().persistent().set(&DataKey::StreamPositions(quest_id, user), &position);
+    }
+
+    /// Resgata o que um usuário acumulou em uma quest Streaming (O(1), independente do número de participantes)
+    pub fn claim_stream(env: Env, quest_id: u64, user: Address) -> u128 {
+        user.require_auth();
+        StorageHelper::require_not_denied(&env, &user);
+
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        if quest.distribution != DistributionType::Streaming {
+            panic_with_error!(&env, Error::InvalidDistribution);
+        }
+
+        let state = StorageHelper::update_stream(&env, &quest);
+        let mut position = StorageHelper::get_stream_position(&env, quest_id, &user);
+
+        if position.is_eligible {
+            position.pending += (state.acc_reward_per_user - position.acc_snapshot) / STREAM_PRECISION;
+            position.acc_snapshot = state.acc_reward_per_user;
+        }
+
+        let amount = position.pending;
+        if amount == 0 {
+            panic_with_error!(&env, Error::NothingToClaim);
+        }
+
+        position.pending = 0;
+        position.claimed += amount;
+        env.storage().persistent().set(&DataKey::StreamPositions(quest_id, user.clone()), &position);
+
+        token::Client::new(&env, &quest.reward_token).transfer(
+            &env.current_contract_address(),
+            &user,
+            &(Self::take_payout_fee(&env, &quest, amount) as i128)
+        );
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "stream_claimed"),), StreamClaimedEvent {
+            quest_id,
+            user,
+            amount,
+        });
+
+        amount
+    }
+
+    /// Reporta a pontuação de um usuário (ex: volume negociado) em uma quest ProRata ou Leaderboard
+    /// Em ProRata, usuários com pontuação passam a ser elegíveis para a divisão do pool
+    /// Em Leaderboard, a pontuação substitui a anterior e só é aceita até o fim da quest
+    pub fn submit_score(env: Env, quest_id: u64, user: Address, score: u128) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        // Apenas o admin da quest (nosso back-end) reporta pontuações
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
+            panic_with_error!(&env, Error::UserNotRegistered);
+        }
+        StorageHelper::require_not_denied(&env, &user);
+
+        match quest.distribution {
+            DistributionType::ProRata => {
+                let mut scores: Map<Address, u128> = env.storage().persistent()
+                    .get(&DataKey::Scores(quest_id))
+                    .unwrap_or(Map::new(&env));
+                scores.set(user.clone(), score);
+                env.storage().persistent().set(&DataKey::Scores(quest_id), &scores);
+
+                let mut participants: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Participants(quest_id))
+                    .unwrap_or(Vec::new(&env));
+                if !participants.contains(&user) {
+                    participants.push_back(user.clone());
+                    env.storage().persistent().set(&DataKey::Participants(quest_id), &participants);
+                }
+            },
+            DistributionType::Leaderboard => {
+                if env.ledger().timestamp() > quest.end_timestamp {
+                    panic_with_error!(&env, Error::QuestExpired);
+                }
+                let mut scores: Map<Address, u128> = env.storage().persistent()
+                    .get(&DataKey::Scores(quest_id))
+                    .unwrap_or(Map::new(&env));
+                let mut participants: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Participants(quest_id))
+                    .unwrap_or(Vec::new(&env));
+
+                // Quem atualiza a pontuação vai para o fim da lista, para que no empate fique à frente quem a atingiu primeiro
+                let index = participants.first_index_of(&user);
+                if let Some(index) = index {
+                    participants.remove(index);
+                }
+
+                // Pontuação zero sai do ranking e libera a vaga
+                if score == 0 {
+                    scores.remove(user.clone());
+                } else {
+                    if index.is_none() && participants.len() >= MAX_LEADERBOARD_ENTRIES {
+                        panic_with_error!(&env, Error::LeaderboardFull);
+                    }
+                    scores.set(user.clone(), score);
+                    participants.push_back(user.clone());
+                }
+                env.storage().persistent().set(&DataKey::Scores(quest_id), &scores);
+                env.storage().persistent().set(&DataKey::Participants(quest_id), &participants);
+            },
+            _ => panic_with_error!(&env, Error::InvalidDistribution),
+        }
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "score_submitted"),), ScoreSubmittedEvent {
+            quest_id,
+            user,
+            score,
+        });
+    }
+
+    /// Define quantos bilhetes um usuário tem no sorteio ponderado (ex: mais bilhetes para mais volume)
+    /// Usuários com bilhetes passam a concorrer; elegíveis sem bilhetes definidos concorrem com 1
+    pub fn set_tickets(env: Env, quest_id: u64, user: Address, tickets: u32) {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        // Apenas o admin da quest (nosso back-end) atribui bilhetes
+        quest.admin.require_auth();
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestNotActive);
+        }
+        if quest.distribution != DistributionType::WeightedRaffle {
+            panic_with_error!(&env, Error::InvalidDistribution);
+        }
+        if !env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone())) {
+            panic_with_error!(&env, Error::UserNotRegistered);
+        }
+        StorageHelper::require_not_denied(&env, &user);
+
+        let mut ticket_table: Map<Address, u32> = env.storage().persistent()
+            .get(&DataKey::Tickets(quest_id))
+            .unwrap_or(Map::new(&env));
+        ticket_table.set(user.clone(), tickets);
+        env.storage().persistent().set(&DataKey::Tickets(quest_id), &ticket_table);
+
+        let mut participants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Participants(quest_id))
+            .unwrap_or(Vec::new(&env));
+        if !participants.contains(&user) {
+            participants.push_back(user.clone());
+            env.storage().persistent().set(&DataKey::Participants(quest_id), &participants);
+        }
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "tickets_assigned"),), TicketsAssignedEvent {
+            quest_id,
+            user,
+            tickets,
+        });
+    }
+
+    /// Resolve uma quest (faz o sorteio se necessário)
+    pub fn resolve_quest(env: Env, quest_id: u64) {
+        let mut quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+        
+        quest.admin.require_auth();
+
+        if env.ledger().timestamp() < quest.end_timestamp {
+            panic_with_error!(&env, Error::QuestNotFinished);
+        }
+
+        if !quest.is_active {
+            panic_with_error!(&env, Error::QuestAlreadyResolved);
         }
 
         // Se for Raffle, faz o sorteio agora
@@ -295,7 +1386,7 @@ impl QuestManager {
             // Lógica de sorteio usando timestamp e hash como semente
             if !participants.is_empty() {
                 let mut pseudo_random_seed = env.ledger().timestamp();
-                let winners_count = quest.max_winners.min(participants.len() as u32);
+                let winners_count = quest.max_winners.min(participants.len());
                 
                 let mut available_participants = participants.clone();
                 
@@ -309,26 +1400,160 @@ impl QuestManager {
                     available_participants.remove(index);
                     
                     // Atualiza a semente para o próximo sorteio
-                    pseudo_random_seed = (pseudo_random_seed.wrapping_mul(1103515245).wrapping_add(12345)) / 65536;
+                    pseudo_random_seed = StorageHelper::next_seed(pseudo_random_seed);
                 }
             }
             
             env.storage().persistent().set(&DataKey::Winners(quest_id), &winners);
         }
+
+        // Se for WeightedRaffle, sorteia proporcionalmente aos bilhetes, sem reposição
+        if quest.distribution == DistributionType::WeightedRaffle {
+            let mut available_entries = StorageHelper::get_ticket_entries(&env, quest_id);
+            let mut total_tickets: u64 = available_entries.iter().map(|entry| entry.tickets as u64).sum();
+
+            let mut winners: Vec<Address> = Vec::new(&env);
+            let mut pseudo_random_seed = env.ledger().timestamp();
+
+            while winners.len() < quest.max_winners && total_tickets > 0 {
+                // Escolhe um bilhete e encontra o participante dono dele
+                let mut ticket = pseudo_random_seed % total_tickets;
+                let mut drawn_index = 0u32;
+                for (index, entry) in available_entries.iter().enumerate() {
+                    if ticket < entry.tickets as u64 {
+                        drawn_index = index as u32;
+                        break;
+                    }
+                    ticket -= entry.tickets as u64;
+                }
+
+                let drawn = available_entries.get(drawn_index).unwrap();
+                total_tickets -= drawn.tickets as u64;
+                winners.push_back(drawn.user);
+                available_entries.remove(drawn_index);
+
+                pseudo_random_seed = StorageHelper::next_seed(pseudo_random_seed);
+            }
+
+            env.storage().persistent().set(&DataKey::Winners(quest_id), &winners);
+        }
+
+        // Se for Leaderboard, os ganhadores são os primeiros do ranking
+        if quest.distribution == DistributionType::Leaderboard {
+            let mut winners: Vec<Address> = Vec::new(&env);
+            for entry in StorageHelper::get_leaderboard(&env, &quest).iter() {
+                winners.push_back(entry.user);
+            }
+
+            env.storage().persistent().set(&DataKey::Winners(quest_id), &winners);
+        }
+
+        // Se for ProRata, divide o pool proporcionalmente às pontuações
+        if quest.distribution == DistributionType::ProRata {
+            let allocations = StorageHelper::allocate_pro_rata(&env, &quest);
+
+            let mut winners: Vec<Address> = Vec::new(&env);
+            for allocation in allocations.iter() {
+                winners.push_back(allocation.winner);
+            }
+
+            env.storage().persistent().set(&DataKey::Winners(quest_id), &winners);
+            env.storage().persistent().set(&DataKey::Allocations(quest_id), &allocations);
+        }
         
+        // Se for Streaming, fecha o acumulador no fim da quest e devolve aos patrocinadores tudo que não foi atribuído
+        if quest.distribution == DistributionType::Streaming {
+            let mut state = StorageHelper::update_stream(&env, &quest);
+            let unassigned = quest.total_reward_pool.saturating_sub(state.distributed);
+            if unassigned > 0 {
+                Self::refund_to_sponsors(&env, &quest, unassigned);
+                quest.total_reward_pool = state.distributed;
+            }
+            state.undistributed = 0;
+            env.storage().persistent().set(&DataKey::Stream(quest_id), &state);
+        }
+
+        // O que o pool tem além dos prêmios volta a quem o financiou, proporcionalmente ao aporte de cada um
+        if quest.distribution != DistributionType::Streaming {
+            let allocated: u128 = StorageHelper::get_winner_prizes(&env, &quest).iter().map(|prize| prize.amount).sum();
+            if quest.total_reward_pool > allocated {
+                Self::refund_to_sponsors(&env, &quest, quest.total_reward_pool - allocated);
+                quest.total_reward_pool = allocated;
+            }
+        }
+
+        // Os tokens adicionais reservados para vagas que ficaram sem ganhador voltam ao admin
+        let prizes_count = StorageHelper::get_winner_prizes(&env, &quest).len() as u128;
+        let mut bonus_rewards = StorageHelper::get_bonus_rewards(&env, quest_id);
+        for index in 0..bonus_rewards.len() {
+            let mut bonus = bonus_rewards.get(index).unwrap();
+            let used = bonus.amount_per_winner * prizes_count;
+            if bonus.total_pool > used {
+                token::Client::new(&env, &bonus.token).transfer(
+                    &env.current_contract_address(),
+                    &quest.admin,
+                    &((bonus.total_pool - used) as i128)
+                );
+                bonus.total_pool = used;
+                bonus_rewards.set(index, bonus);
+            }
+        }
+        if !bonus_rewards.is_empty() {
+            env.storage().persistent().set(&DataKey::BonusRewards(quest_id), &bonus_rewards);
+        }
+
+        // A parte do admin nas taxas de inscrição é liberada na resolução
+        let entry_fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest_id));
+        if let Some(mut fee) = entry_fee {
+            if fee.collected_cut > 0 {
+                token::Client::new(&env, &quest.reward_token).transfer(
+                    &env.current_contract_address(),
+                    &quest.admin,
+                    &(fee.collected_cut as i128)
+                );
+                fee.collected_cut = 0;
+                env.storage().persistent().set(&DataKey::EntryFee(quest_id), &fee);
+            }
+        }
+
+        // O vesting começa a contar no momento da resolução
+        let vesting: Option<VestingSchedule> = env.storage().persistent().get(&DataKey::Vesting(quest_id));
+        if let Some(mut schedule) = vesting {
+            schedule.start_timestamp = Some(env.ledger().timestamp());
+            env.storage().persistent().set(&DataKey::Vesting(quest_id), &schedule);
+        }
+
         // Desativa a quest
         let mut quest_to_update = quest;
         quest_to_update.is_active = false;
         env.storage().persistent().set(&DataKey::Quests(quest_id), &quest_to_update);
 
+        // Quem ficou na fila de espera recebe a taxa de volta
+        Self::release_waitlist(&env, &quest_to_update);
+
         let winners: Vec<Address> = env.storage().persistent()
             .get(&DataKey::Winners(quest_id))
             .unwrap_or(Vec::new(&env));
 
+        // Cada ganhador conta uma vitória por quest, mesmo com várias entradas premiadas
+        let xp_rewards = StorageHelper::get_xp_rewards(&env, quest_id);
+        let mut counted: Vec<Address> = Vec::new(&env);
+        for prize in StorageHelper::get_winner_prizes(&env, &quest_to_update).iter() {
+            let mut stats = StorageHelper::get_user_stats(&env, &prize.winner);
+            stats.total_rewards += prize.amount;
+            if !counted.contains(&prize.winner) {
+                counted.push_back(prize.winner.clone());
+                Self::mint_badge(&env, quest_id, &prize.winner, BadgeKind::Winner);
+                stats.total_won += 1;
+                StorageHelper::add_xp(&env, &mut stats, &prize.winner, xp_rewards.win);
+            }
+            StorageHelper::set_user_stats(&env, &prize.winner, stats);
+        }
+
         // Emit event
         env.events().publish((Symbol::new(&env, "quest_resolved"),), QuestResolvedEvent {
             quest_id,
-            winners_count: winners.len() as u32,
+            winners_count: winners.len(),
         });
     }
 
@@ -344,6 +1569,15 @@ impl QuestManager {
             panic_with_error!(&env, Error::QuestNotResolved);
         }
 
+        // Recompensas com vesting são liberadas aos poucos via claim_vested
+        if env.storage().persistent().has(&DataKey::Vesting(quest_id)) {
+            panic_with_error!(&env, Error::RewardsVesting);
+        }
+        // Pagamentos que falham não revertem a distribuição, então ela só pode acontecer uma vez
+        if env.storage().persistent().has(&DataKey::Distributed(quest_id)) {
+            panic_with_error!(&env, Error::RewardsAlreadyDistributed);
+        }
+
         let winners: Vec<Address> = env.storage().persistent()
             .get(&DataKey::Winners(quest_id))
             .unwrap_or(Vec::new(&env));
@@ -352,17 +1586,289 @@ impl QuestManager {
             panic_with_error!(&env, Error::NoWinners);
         }
 
-        let reward_token_client = token::Client::new(&env, &quest.reward_token);
+        let bonus_rewards = StorageHelper::get_bonus_rewards(&env, quest_id);
 
-        for winner in winners.iter() {
-            reward_token_client.transfer(
-                &env.current_contract_address(),
-                &winner,
-                &(quest.reward_per_winner as i128)
-            );
+        let prizes = StorageHelper::get_winner_prizes(&env, &quest);
+        env.storage().persistent().set(&DataKey::Distributed(quest_id), &true);
+
+        // Cada ganhador recebe o pacote completo de tokens na mesma transação
+        // Uma transferência que falha não reverte as demais: o valor fica devido ao ganhador
+        for prize in prizes.iter() {
+            let amount = Self::take_payout_fee(&env, &quest, prize.amount);
+            Self::pay_or_owe(&env, quest_id, &quest.reward_token, &prize.winner, amount);
+
+            for bonus in bonus_rewards.iter() {
+                Self::pay_or_owe(&env, quest_id, &bonus.token, &prize.winner, bonus.amount_per_winner);
+            }
+        }
+
+    }
+
+    /// Resgata os pagamentos que falharam na distribuição
+    /// Tokens que ainda não podem ser transferidos ao usuário continuam devidos
+    pub fn claim_owed(env: Env, quest_id: u64, user: Address) -> Vec<OwedReward> {
+        user.require_auth();
+        StorageHelper::require_not_denied(&env, &user);
+
+        let owed = StorageHelper::get_owed_rewards(&env, quest_id, &user);
+        let mut paid: Vec<OwedReward> = Vec::new(&env);
+        let mut remaining: Vec<OwedReward> = Vec::new(&env);
+
+        for entry in owed.iter() {
+            if !Self::try_pay(&env, &entry.token, &user, entry.amount) {
+                remaining.push_back(entry);
+                continue;
+            }
+
+            // Emit event
+            env.events().publish((Symbol::new(&env, "owed_claimed"),), OwedClaimedEvent {
+                quest_id,
+                user: user.clone(),
+                token: entry.token.clone(),
+                amount: entry.amount,
+            });
+            paid.push_back(entry);
+        }
+
+        if paid.is_empty() {
+            panic_with_error!(&env, Error::NothingToClaim);
+        }
+
+        let key = DataKey::OwedRewards(quest_id, user.clone());
+        if remaining.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &remaining);
+        }
+
+        paid
+    }
+
+    /// Resgata a parte já liberada das recompensas de um ganhador em uma quest com vesting
+    /// Os tokens adicionais do pacote são liberados na mesma proporção do reward_token
+    pub fn claim_vested(env: Env, quest_id: u64, user: Address) -> u128 {
+        user.require_auth();
+        StorageHelper::require_not_denied(&env, &user);
+
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        let status = StorageHelper::get_vesting_status(&env, &quest, &user);
+        let claimable = status.vested - status.claimed;
+        if claimable == 0 {
+            panic_with_error!(&env, Error::NothingToClaim);
+        }
+
+        let total_claimed = status.claimed + claimable;
+        env.storage().persistent().set(&DataKey::VestingClaimed(quest_id, user.clone()), &total_claimed);
+
+        token::Client::new(&env, &quest.reward_token).transfer(
+            &env.current_contract_address(),
+            &user,
+            &(Self::take_payout_fee(&env, &quest, claimable) as i128)
+        );
+
+        // Os tokens adicionais são pagos uma vez por prêmio, como em distribute_rewards
+        let prize_count = StorageHelper::get_winner_prizes(&env, &quest).iter()
+            .filter(|prize| prize.winner == user)
+            .count() as u128;
+        for bonus in StorageHelper::get_bonus_rewards(&env, quest_id).iter() {
+            let bonus_total = bonus.amount_per_winner * prize_count;
+            let bonus_amount = bonus_total * total_claimed / status.total
+                - bonus_total * status.claimed / status.total;
+            // Um token adicional que não pode ser transferido fica devido, sem travar o resgate do principal
+            if bonus_amount > 0 {
+                Self::pay_or_owe(&env, quest_id, &bonus.token, &user, bonus_amount);
+            }
+        }
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "vested_claimed"),), VestedClaimedEvent {
+            quest_id,
+            user,
+            amount: claimable,
+            total_claimed,
+        });
+
+        claimable
+    }
+
+    /// Configura o dono do protocolo, a tesouraria e a taxa cobrada sobre as campanhas
+    /// Só pode ser chamada uma vez, pela conta que implantou o contrato com o `salt` informado
+    pub fn initialize_protocol(env: Env, owner: Address, salt: BytesN<32>, treasury: Address, fee_bps: u32, fee_base: FeeBase) {
+        owner.require_auth();
+
+        if env.storage().instance().has(&DataKey::ProtocolConfig) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+        // O endereço do contrato deriva de quem o implantou e do salt; só esse deployer vira owner
+        if env.deployer().with_address(owner.clone(), salt).deployed_address() != env.current_contract_address() {
+            panic_with_error!(&env, Error::Unauthorized);
+        }
+        if fee_bps > MAX_PROTOCOL_FEE_BPS {
+            panic_with_error!(&env, Error::InvalidFee);
+        }
+
+        env.storage().instance().set(&DataKey::ProtocolConfig, &ProtocolConfig {
+            owner,
+            treasury,
+            fee_bps,
+            fee_base,
+        });
+    }
+
+    /// Altera a taxa do protocolo (vale para quests criadas a partir de agora)
+    pub fn set_protocol_fee(env: Env, fee_bps: u32, fee_base: FeeBase) {
+        let mut config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        if fee_bps > MAX_PROTOCOL_FEE_BPS {
+            panic_with_error!(&env, Error::InvalidFee);
+        }
+
+        config.fee_bps = fee_bps;
+        config.fee_base = fee_base;
+        env.storage().instance().set(&DataKey::ProtocolConfig, &config);
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "protocol_fee_updated"),), ProtocolFeeUpdatedEvent {
+            fee_bps,
+            fee_base,
+        });
+    }
+
+    /// Altera o endereço que recebe as taxas do protocolo
+    pub fn set_treasury(env: Env, treasury: Address) {
+        let mut config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        config.treasury = treasury;
+        env.storage().instance().set(&DataKey::ProtocolConfig, &config);
+    }
+
+    /// Isenta (ou deixa de isentar) um criador de quests da taxa do protocolo
+    pub fn set_fee_exemption(env: Env, creator: Address, exempt: bool) {
+        let config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        if exempt {
+            env.storage().persistent().set(&DataKey::FeeExempt(creator), &true);
+        } else {
+            env.storage().persistent().remove(&DataKey::FeeExempt(creator));
+        }
+    }
+
+    /// Define o endereço de compliance, responsável pela denylist global
+    pub fn set_compliance(env: Env, compliance: Address) {
+        let config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        env.storage().instance().set(&DataKey::Compliance, &compliance);
+    }
+
+    /// Encerra a temporada de XP atual e inicia a próxima (apenas o owner)
+    /// O XP total dos usuários é mantido; o XP e o ranking da nova temporada começam do zero
+    pub fn start_season(env: Env) -> u32 {
+        let config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        let season = StorageHelper::get_current_season(&env) + 1;
+        env.storage().instance().set(&DataKey::Season, &season);
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "season_started"),), SeasonStartedEvent {
+            season,
+            timestamp: env.ledger().timestamp(),
+        });
+
+        season
+    }
+
+    /// Bloqueia (ou desbloqueia) um endereço em todas as quests: registro, elegibilidade e pagamentos
+    pub fn set_denied(env: Env, user: Address, denied: bool) {
+        let compliance: Address = env.storage().instance()
+            .get(&DataKey::Compliance)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized));
+        compliance.require_auth();
+
+        if denied {
+            env.storage().persistent().set(&DataKey::Denied(user.clone()), &true);
+        } else {
+            env.storage().persistent().remove(&DataKey::Denied(user.clone()));
+        }
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "denylist_updated"),), DenylistUpdatedEvent {
+            user,
+            denied,
+        });
+    }
+
+    /// Define o registrar confiável que pode atestar quando um endereço foi visto pela primeira vez
+    pub fn set_registrar(env: Env, registrar: Address) {
+        let config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        env.storage().instance().set(&DataKey::Registrar, &registrar);
+    }
+
+    /// Atesta a idade de um endereço (ex: criação da conta observada off-chain)
+    /// Só pode antecipar o registro existente, nunca adiá-lo
+    pub fn attest_first_seen(env: Env, user: Address, timestamp: u64) {
+        let registrar: Address = env.storage().instance()
+            .get(&DataKey::Registrar)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotInitialized));
+        registrar.require_auth();
+
+        if timestamp > env.ledger().timestamp() {
+            panic_with_error!(&env, Error::InvalidDuration);
+        }
+
+        let key = DataKey::FirstSeen(user);
+        let current: Option<u64> = env.storage().persistent().get(&key);
+        if current.is_none_or(|first_seen| timestamp < first_seen) {
+            env.storage().persistent().set(&key, &timestamp);
         }
     }
 
+    /// Registra a primeira interação do usuário com o contrato, sem se registrar em nenhuma quest
+    /// Útil para começar a contar a idade da conta antes de quests com min_account_age
+    pub fn check_in(env: Env, user: Address) {
+        user.require_auth();
+
+        StorageHelper::record_first_seen(&env, &user);
+    }
+
+    /// Envia para a tesouraria as taxas acumuladas em um token
+    pub fn withdraw_protocol_fees(env: Env, token: Address) -> u128 {
+        let config = StorageHelper::get_protocol_config(&env);
+        config.owner.require_auth();
+
+        let amount: u128 = env.storage().persistent()
+            .get(&DataKey::ProtocolFees(token.clone()))
+            .unwrap_or(0);
+        if amount == 0 {
+            panic_with_error!(&env, Error::NothingToClaim);
+        }
+
+        env.storage().persistent().set(&DataKey::ProtocolFees(token.clone()), &0u128);
+        token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &config.treasury,
+            &(amount as i128)
+        );
+
+        // Emit event
+        env.events().publish((Symbol::new(&env, "protocol_fees_withdrawn"),), ProtocolFeesWithdrawnEvent {
+            token,
+            treasury: config.treasury,
+            amount,
+        });
+
+        amount
+    }
+
     // VIEW FUNCTIONS
     
     /// Obtém informações de uma quest específica
@@ -389,22 +1895,232 @@ impl QuestManager {
                 active_quests.push_back(quest);
             }
         }
-        
-        active_quests
-    }
+        
+        active_quests
+    }
+
+    /// Obtém os participantes de uma quest
+    pub fn get_participants(env: Env, quest_id: u64) -> Vec<Address> {
+        env.storage().persistent()
+            .get(&DataKey::Participants(quest_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Obtém os ganhadores de uma quest
+    pub fn get_winners(env: Env, quest_id: u64) -> Vec<Address> {
+        env.storage().persistent()
+            .get(&DataKey::Winners(quest_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Obtém a tabela de prêmios de uma quest (vazia se todos recebem reward_per_winner)
+    pub fn get_prize_table(env: Env, quest_id: u64) -> Vec<PrizeTier> {
+        env.storage().persistent()
+            .get(&DataKey::PrizeTable(quest_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Obtém a posição e o prêmio de cada ganhador de uma quest
+    pub fn get_winner_prizes(env: Env, quest_id: u64) -> Vec<WinnerPrize> {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        StorageHelper::get_winner_prizes(&env, &quest)
+    }
+
+    /// Obtém a pontuação reportada para um usuário em uma quest
+    pub fn get_score(env: Env, quest_id: u64, user: Address) -> u128 {
+        let scores: Map<Address, u128> = env.storage().persistent()
+            .get(&DataKey::Scores(quest_id))
+            .unwrap_or(Map::new(&env));
+        scores.get(user).unwrap_or(0)
+    }
+
+    /// Obtém a tabela de bilhetes do sorteio ponderado, consultável antes do sorteio
+    pub fn get_tickets(env: Env, quest_id: u64) -> Vec<TicketEntry> {
+        StorageHelper::get_ticket_entries(&env, quest_id)
+    }
+
+    /// Obtém o ranking atual de uma quest Leaderboard, do primeiro ao último colocado
+    pub fn get_leaderboard(env: Env, quest_id: u64) -> Vec<LeaderboardEntry> {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        StorageHelper::get_leaderboard(&env, &quest)
+    }
+
+    /// Obtém quais sub-tarefas de uma quest composta o usuário já concluiu, na ordem das tarefas
+    pub fn get_task_progress(env: Env, quest_id: u64, user: Address) -> Vec<bool> {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+        StorageHelper::get_task_completions(&env, &quest, &user)
+    }
+
+    /// Obtém o progresso do usuário em cada tarefa da quest (uma única tarefa em quests simples)
+    pub fn get_progress(env: Env, quest_id: u64, user: Address) -> Vec<TaskProgress> {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        let values = StorageHelper::get_progress_values(&env, &quest, &user);
+        let completions = StorageHelper::get_task_completions(&env, &quest, &user);
+        let is_composite = matches!(quest.quest_type, QuestType::Composite(..));
+
+        let mut progress = Vec::new(&env);
+        for (index, task) in StorageHelper::get_tasks(&env, &quest).iter().enumerate() {
+            let current = values.get(index as u32).unwrap();
+            let target = StorageHelper::task_target(&task);
+            let completed = if is_composite {
+                completions.get(index as u32).unwrap()
+            } else {
+                current >= target || StorageHelper::has_qualified(&env, &quest, &user)
+            };
+            progress.push_back(TaskProgress { task, current, target, completed });
+        }
+
+        progress
+    }
+
+    /// Obtém os badges conquistados por um usuário
+    pub fn get_badges(env: Env, user: Address) -> Vec<Badge> {
+        StorageHelper::get_badges(&env, &user)
+    }
+
+    /// Obtém quem indicou um usuário, se houver
+    pub fn get_referrer(env: Env, user: Address) -> Option<Address> {
+        let referral: Option<Referral> = env.storage().persistent().get(&DataKey::Referrer(user));
+        referral.map(|referral| referral.referrer)
+    }
+
+    /// Obtém quantos indicados de um endereço já se tornaram elegíveis
+    pub fn get_referral_count(env: Env, referrer: Address) -> u32 {
+        env.storage().persistent()
+            .get(&DataKey::Referrals(referrer))
+            .unwrap_or(0)
+    }
+
+    /// Obtém os pré-requisitos de uma quest
+    pub fn get_prerequisites(env: Env, quest_id: u64) -> Vec<Prerequisite> {
+        StorageHelper::get_prerequisites(&env, quest_id)
+    }
+
+    /// Obtém as quests que têm esta como pré-requisito (próximos passos da jornada)
+    pub fn get_dependents(env: Env, quest_id: u64) -> Vec<u64> {
+        env.storage().persistent()
+            .get(&DataKey::Dependents(quest_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Obtém a cadeia completa até uma quest, em ordem de execução e terminando nela
+    pub fn get_quest_chain(env: Env, quest_id: u64) -> Vec<u64> {
+        StorageHelper::get_quest_chain(&env, quest_id)
+    }
+
+    /// Verifica se o usuário já cumpriu os pré-requisitos de uma quest
+    pub fn has_met_prerequisites(env: Env, quest_id: u64, user: Address) -> bool {
+        StorageHelper::has_met_prerequisites(&env, quest_id, &user)
+    }
+
+    /// Obtém o modo da allowlist de uma quest, se houver
+    pub fn get_allowlist_mode(env: Env, quest_id: u64) -> Option<AllowlistMode> {
+        env.storage().persistent().get(&DataKey::Allowlist(quest_id))
+    }
+
+    /// Verifica se um usuário pode se registrar em uma quest (allowlist e denylist)
+    pub fn is_allowlisted(env: Env, quest_id: u64, user: Address) -> bool {
+        !StorageHelper::is_denied(&env, &user) && StorageHelper::is_allowlisted(&env, quest_id, &user)
+    }
+
+    /// Verifica se um endereço está na denylist global
+    pub fn is_denied(env: Env, user: Address) -> bool {
+        StorageHelper::is_denied(&env, &user)
+    }
+
+    /// Obtém quando um endereço foi visto pela primeira vez, se já foi
+    pub fn get_first_seen(env: Env, user: Address) -> Option<u64> {
+        env.storage().persistent().get(&DataKey::FirstSeen(user))
+    }
+
+    /// Obtém o endereço de compliance, se definido
+    pub fn get_compliance(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Compliance)
+    }
+
+    /// Obtém a fila de espera de uma quest, na ordem de promoção
+    pub fn get_waitlist(env: Env, quest_id: u64) -> Vec<Address> {
+        StorageHelper::get_waitlist(&env, quest_id)
+    }
+
+    /// Verifica se a quest aceita novos registros (ainda que na fila de espera)
+    pub fn is_registration_open(env: Env, quest_id: u64) -> bool {
+        let quest: Option<Quest> = env.storage().persistent().get(&DataKey::Quests(quest_id));
+        match quest {
+            Some(quest) => quest.is_active
+                && env.ledger().timestamp() <= quest.end_timestamp
+                && !env.storage().persistent().has(&DataKey::RegistrationClosed(quest_id)),
+            None => false,
+        }
+    }
+
+    /// Obtém o nonce que o próximo registro delegado do usuário deve assinar
+    pub fn get_nonce(env: Env, user: Address) -> u64 {
+        env.storage().persistent()
+            .get(&DataKey::Nonce(user))
+            .unwrap_or(0)
+    }
+
+    /// Obtém as configurações avançadas de uma quest, se houver
+    pub fn get_quest_config(env: Env, quest_id: u64) -> Option<QuestConfig> {
+        env.storage().persistent().get(&DataKey::Config(quest_id))
+    }
+
+    /// Obtém quantas entradas (vagas ou bilhetes) um usuário acumulou em uma quest
+    pub fn get_entries(env: Env, quest_id: u64, user: Address) -> u32 {
+        env.storage().persistent()
+            .get(&DataKey::Entries(quest_id, user))
+            .unwrap_or(0)
+    }
+
+    /// Obtém o cronograma de vesting de uma quest, se houver
+    pub fn get_vesting(env: Env, quest_id: u64) -> Option<VestingSchedule> {
+        env.storage().persistent().get(&DataKey::Vesting(quest_id))
+    }
+
+    /// Obtém os valores liberados, resgatados e restantes de um ganhador
+    pub fn get_vesting_status(env: Env, quest_id: u64, user: Address) -> VestingStatus {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        StorageHelper::get_vesting_status(&env, &quest, &user)
+    }
+
+    /// Obtém quanto um usuário pode resgatar agora em uma quest Streaming
+    pub fn get_stream_claimable(env: Env, quest_id: u64, user: Address) -> u128 {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        if quest.distribution != DistributionType::Streaming {
+            return 0;
+        }
 
-    /// Obtém os participantes de uma quest
-    pub fn get_participants(env: Env, quest_id: u64) -> Vec<Address> {
-        env.storage().persistent()
-            .get(&DataKey::Participants(quest_id))
-            .unwrap_or(Vec::new(&env))
+        let state = StorageHelper::preview_stream(&env, &quest);
+        let position = StorageHelper::get_stream_position(&env, quest_id, &user);
+
+        if position.is_eligible {
+            position.pending + (state.acc_reward_per_user - position.acc_snapshot) / STREAM_PRECISION
+        } else {
+            position.pending
+        }
     }
 
-    /// Obtém os ganhadores de uma quest
-    pub fn get_winners(env: Env, quest_id: u64) -> Vec<Address> {
-        env.storage().persistent()
-            .get(&DataKey::Winners(quest_id))
-            .unwrap_or(Vec::new(&env))
+    /// Obtém a posição de um usuário em uma quest Streaming
+    pub fn get_stream_position(env: Env, quest_id: u64, user: Address) -> StreamPosition {
+        StorageHelper::get_stream_position(&env, quest_id, &user)
     }
 
     /// Verifica se um usuário está registrado em uma quest
@@ -419,6 +2135,78 @@ impl QuestManager {
             .unwrap_or(Vec::new(&env))
     }
 
+    /// Obtém as contribuições de cada patrocinador de uma quest
+    pub fn get_sponsors(env: Env, quest_id: u64) -> Vec<SponsorContribution> {
+        StorageHelper::get_sponsor_contributions(&env, quest_id)
+    }
+
+    /// Obtém o total aportado por um patrocinador em uma quest
+    pub fn get_contribution(env: Env, quest_id: u64, sponsor: Address) -> u128 {
+        env.storage().persistent()
+            .get(&DataKey::Contributions(quest_id, sponsor))
+            .unwrap_or(0)
+    }
+
+    /// Obtém o pacote de recompensas por ganhador, começando pelo reward_token principal
+    pub fn get_reward_bundle(env: Env, quest_id: u64) -> Vec<BonusReward> {
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::QuestNotFound));
+
+        let mut bundle = Vec::new(&env);
+        bundle.push_back(BonusReward {
+            token: quest.reward_token,
+            amount_per_winner: quest.reward_per_winner,
+            total_pool: quest.total_reward_pool,
+        });
+        bundle.append(&StorageHelper::get_bonus_rewards(&env, quest_id));
+
+        bundle
+    }
+
+    /// Obtém a taxa de inscrição de uma quest, se houver
+    pub fn get_entry_fee(env: Env, quest_id: u64) -> Option<EntryFee> {
+        env.storage().persistent().get(&DataKey::EntryFee(quest_id))
+    }
+
+    /// Obtém a configuração do protocolo
+    pub fn get_protocol_config(env: Env) -> ProtocolConfig {
+        StorageHelper::get_protocol_config(&env)
+    }
+
+    /// Obtém a taxa do protocolo aplicada a uma quest, se houver
+    pub fn get_quest_fee(env: Env, quest_id: u64) -> Option<QuestFee> {
+        env.storage().persistent().get(&DataKey::QuestFee(quest_id))
+    }
+
+    /// Obtém as taxas do protocolo acumuladas em um token e ainda não sacadas
+    pub fn get_protocol_fees(env: Env, token: Address) -> u128 {
+        env.storage().persistent()
+            .get(&DataKey::ProtocolFees(token))
+            .unwrap_or(0)
+    }
+
+    /// Verifica se um criador é isento da taxa do protocolo
+    pub fn is_fee_exempt(env: Env, creator: Address) -> bool {
+        env.storage().persistent()
+            .get(&DataKey::FeeExempt(creator))
+            .unwrap_or(false)
+    }
+
+    /// Obtém os pagamentos ainda devidos a um ganhador
+    pub fn get_owed_rewards(env: Env, quest_id: u64, user: Address) -> Vec<OwedReward> {
+        StorageHelper::get_owed_rewards(&env, quest_id, &user)
+    }
+
+    /// Obtém o endereço do Stellar Asset Contract do XLM nativo nesta rede
+    /// Pode ser usado diretamente como reward_token ao criar quests
+    pub fn get_native_token(env: Env) -> Address {
+        // Asset::Native serializado em XDR
+        env.deployer()
+            .with_stellar_asset(Bytes::from_array(&env, &[0, 0, 0, 0]))
+            .deployed_address()
+    }
+
     /// Obtém o contador atual de quests
     pub fn get_quest_counter(env: Env) -> u64 {
         env.storage().instance().get(&DataKey::QuestCounter).unwrap_or(0)
@@ -440,15 +2228,16 @@ impl QuestManager {
 
         // Conta registrations manualmente (não há forma direta no Soroban)
         let total_registered = match quest.distribution {
-            DistributionType::Raffle => participants.len() as u32,
-            DistributionType::Fcfs => winners.len() as u32,
+            DistributionType::Raffle | DistributionType::ProRata | DistributionType::WeightedRaffle | DistributionType::Leaderboard => participants.len(),
+            DistributionType::Fcfs => winners.len(),
+            DistributionType::Streaming => participants.len(),
         };
 
         QuestStats {
             quest_id,
             total_registered,
-            total_eligible: participants.len() as u32,
-            total_winners: winners.len() as u32,
+            total_eligible: participants.len(),
+            total_winners: winners.len(),
             is_resolved: !quest.is_active,
             time_remaining: if env.ledger().timestamp() < quest.end_timestamp {
                 quest.end_timestamp - env.ledger().timestamp()
@@ -458,9 +2247,24 @@ impl QuestManager {
         }
     }
 
-    /// Obtém estatísticas de participação de um usuário
+    /// Obtém as estatísticas e o XP acumulados de um usuário
     pub fn get_user_stats(env: Env, user: Address) -> UserStats {
-        StorageHelper::get_user_participation_stats(&env, &user)
+        StorageHelper::get_user_stats(&env, &user)
+    }
+
+    /// Obtém a temporada de XP atual
+    pub fn get_current_season(env: Env) -> u32 {
+        StorageHelper::get_current_season(&env)
+    }
+
+    /// Obtém o XP de um usuário em uma temporada
+    pub fn get_season_xp(env: Env, season: u32, user: Address) -> u128 {
+        StorageHelper::get_season_xp(&env, season, &user)
+    }
+
+    /// Obtém os usuários com mais XP em uma temporada, do maior para o menor
+    pub fn get_top_users(env: Env, season: u32) -> Vec<LeaderboardEntry> {
+        StorageHelper::get_top_users(&env, season)
     }
 
     /// Função administrativa para cancelar uma quest
@@ -475,23 +2279,592 @@ impl QuestManager {
             panic_with_error!(&env, Error::QuestNotActive);
         }
 
+        // No streaming, o que já foi atribuído aos usuários continua resgatável
+        let refundable_pool = if quest.distribution == DistributionType::Streaming {
+            quest.total_reward_pool - StorageHelper::update_stream(&env, &quest).distributed
+        } else {
+            quest.total_reward_pool
+        };
+
         // Desativa a quest
         quest.is_active = false;
         env.storage().persistent().set(&DataKey::Quests(quest_id), &quest);
 
-        // Retorna os fundos para o admin
-        let reward_token_client = token::Client::new(&env, &quest.reward_token);
-        reward_token_client.transfer(
-            &env.current_contract_address(),
-            &quest.admin,
-            &(quest.total_reward_pool as i128)
-        );
+        // Retorna os fundos aos patrocinadores, proporcionalmente ao que cada um aportou
+        Self::refund_to_sponsors(&env, &quest, refundable_pool);
+
+        // A parte do admin nas taxas de inscrição fica devida a quem pagou, resgatável via claim_owed
+        // Sem transferências no laço, um pagador inacessível não impede o cancelamento
+        let entry_fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest_id));
+        if let Some(mut fee) = entry_fee {
+            let cut_per_entry = fee.amount * fee.admin_cut_bps as u128 / 10_000;
+            if cut_per_entry > 0 {
+                let registrants: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Registrants(quest_id))
+                    .unwrap_or(Vec::new(&env));
+                for registrant in registrants.iter() {
+                    let payer = StorageHelper::get_entry_fee_payer(&env, quest_id, &registrant);
+                    StorageHelper::add_owed_reward(&env, quest_id, &payer, &quest.reward_token, cut_per_entry);
+                }
+            }
+            fee.collected_cut = 0;
+            env.storage().persistent().set(&DataKey::EntryFee(quest_id), &fee);
+        }
+
+        Self::release_waitlist(&env, &quest);
+
+        // Os tokens adicionais foram depositados pelo admin e voltam para ele
+        for bonus in StorageHelper::get_bonus_rewards(&env, quest_id).iter() {
+            if bonus.total_pool > 0 {
+                token::Client::new(&env, &bonus.token).transfer(
+                    &env.current_contract_address(),
+                    &quest.admin,
+                    &(bonus.total_pool as i128)
+                );
+            }
+        }
 
         // Emit event
         env.events().publish((Symbol::new(&env, "quest_cancelled"),), quest_id);
     }
 }
 
+impl QuestManager {
+    /// Grava o progresso de uma tarefa e conclui a tarefa quando ele passa pela meta
+    fn update_task_progress(env: &Env, quest: &Quest, user: &Address, task_index: u32, value: u128) {
+        let target = StorageHelper::task_target(&StorageHelper::get_tasks(env, quest).get(task_index).unwrap());
+
+        let mut progress = StorageHelper::get_progress_values(env, quest, user);
+        let previous = progress.get(task_index).unwrap();
+        progress.set(task_index, value);
+        env.storage().persistent().set(&DataKey::Progress(quest.id, user.clone()), &progress);
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "progress_updated"),), ProgressUpdatedEvent {
+            quest_id: quest.id,
+            user: user.clone(),
+            task_index,
+            current: value,
+            target,
+        });
+
+        // Só a passagem pela meta conta, para reportes repetidos não gerarem entradas extras
+        if previous >= target || value < target {
+            return;
+        }
+        if let QuestType::Composite(..) = quest.quest_type {
+            Self::record_task_completion(env, quest, user, task_index);
+        } else if quest.distribution != DistributionType::Leaderboard {
+            Self::make_eligible(env, quest, user);
+        }
+    }
+
+    /// Marca uma sub-tarefa como concluída e torna o usuário elegível ao atingir o mínimo exigido
+    fn record_task_completion(env: &Env, quest: &Quest, user: &Address, task_index: u32) {
+        let mut completions = StorageHelper::get_task_completions(env, quest, user);
+        if completions.get(task_index).unwrap() {
+            return;
+        }
+        completions.set(task_index, true);
+        env.storage().persistent().set(&DataKey::TaskCompletions(quest.id, user.clone()), &completions);
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "task_completed"),), TaskCompletedEvent {
+            quest_id: quest.id,
+            user: user.clone(),
+            task_index,
+        });
+
+        // No Leaderboard o ranking vem só das pontuações, então concluir tarefas não torna elegível
+        if quest.distribution != DistributionType::Leaderboard && StorageHelper::has_completed_tasks(env, quest, user) {
+            Self::make_eligible(env, quest, user);
+        }
+    }
+
+    /// Torna o usuário elegível conforme o tipo de distribuição da quest
+    fn make_eligible(env: &Env, quest: &Quest, user: &Address) {
+        let was_qualified = StorageHelper::has_qualified(env, quest, user);
+
+        // Lógica de adição baseada no tipo de distribuição
+        match quest.distribution {
+            DistributionType::Fcfs => {
+                let mut winners: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Winners(quest.id))
+                    .unwrap_or(Vec::new(env));
+                
+                // Cada conclusão ocupa uma vaga, até o limite de entradas do usuário
+                if winners.len() < quest.max_winners && StorageHelper::try_add_entry(env, quest.id, user) {
+                    winners.push_back(user.clone());
+                    env.storage().persistent().set(&DataKey::Winners(quest.id), &winners);
+                }
+            },
+            // No Raffle cada conclusão é um bilhete a mais, até o limite de entradas do usuário
+            DistributionType::Raffle => {
+                if StorageHelper::try_add_entry(env, quest.id, user) {
+                    let mut participants: Vec<Address> = env.storage().persistent()
+                        .get(&DataKey::Participants(quest.id))
+                        .unwrap_or(Vec::new(env));
+                    participants.push_back(user.clone());
+                    env.storage().persistent().set(&DataKey::Participants(quest.id), &participants);
+                }
+            },
+            DistributionType::ProRata | DistributionType::WeightedRaffle => {
+                let mut participants: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Participants(quest.id))
+                    .unwrap_or(Vec::new(env));
+                
+                if !participants.contains(user) { // Evita adicionar o mesmo participante duas vezes
+                    participants.push_back(user.clone());
+                    env.storage().persistent().set(&DataKey::Participants(quest.id), &participants);
+                }
+            },
+            // No Leaderboard a classificação vem apenas das pontuações enviadas
+            DistributionType::Leaderboard => {
+                panic_with_error!(env, Error::InvalidDistribution);
+            },
+            DistributionType::Streaming => {
+                let mut state = StorageHelper::update_stream(env, quest);
+                let mut position = StorageHelper::get_stream_position(env, quest.id, user);
+
+                // Começa a acumular a partir de agora, respeitando o limite de elegíveis simultâneos
+                if !position.is_eligible && state.eligible_count < quest.max_winners {
+                    position.is_eligible = true;
+                    position.acc_snapshot = state.acc_reward_per_user;
+                    state.eligible_count += 1;
+
+                    env.storage().persistent().set(&DataKey::Stream(quest.id), &state);
+                    env.storage().persistent().set(&DataKey::StreamPositions(quest.id, user.clone()), &position);
+
+                    let mut participants: Vec<Address> = env.storage().persistent()
+                        .get(&DataKey::Participants(quest.id))
+                        .unwrap_or(Vec::new(env));
+                    if !participants.contains(user) {
+                        participants.push_back(user.clone());
+                        env.storage().persistent().set(&DataKey::Participants(quest.id), &participants);
+                    }
+                }
+            }
+        }
+
+        // A primeira elegibilidade do usuário conta como indicação bem-sucedida
+        if !was_qualified && StorageHelper::has_qualified(env, quest, user) {
+            Self::mint_badge(env, quest.id, user, BadgeKind::Completion);
+
+            let mut stats = StorageHelper::get_user_stats(env, user);
+            stats.total_completed += 1;
+            StorageHelper::add_xp(env, &mut stats, user, StorageHelper::get_xp_rewards(env, quest.id).completion);
+            StorageHelper::set_user_stats(env, user, stats);
+
+            Self::credit_referral(env, quest.id, user);
+        }
+    }
+
+    /// Devolve parte do pool aos patrocinadores, proporcionalmente ao que cada um aportou
+    /// Sem patrocinadores registrados, o valor volta ao admin
+    fn refund_to_sponsors(env: &Env, quest: &Quest, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+
+        let sponsors = StorageHelper::get_sponsor_contributions(env, quest.id);
+        let total_contributed: u128 = sponsors.iter().map(|c| c.amount).sum();
+        if sponsors.is_empty() || total_contributed == 0 {
+            Self::pay_or_owe(env, quest.id, &quest.reward_token, &quest.admin, amount);
+            return;
+        }
+
+        let mut remaining = amount;
+        for (index, contribution) in sponsors.iter().enumerate() {
+            // O último patrocinador recebe o resto da divisão
+            let refund = if index as u32 == sponsors.len() - 1 {
+                remaining
+            } else {
+                StorageHelper::mul_div(env, amount, contribution.amount, total_contributed)
+            };
+            remaining -= refund;
+
+            Self::pay_or_owe(env, quest.id, &quest.reward_token, &contribution.sponsor, refund);
+        }
+    }
+
+    /// Concede o badge da quest ao usuário, se a quest concede badge nessa conquista e ele ainda não o tem
+    fn mint_badge(env: &Env, quest_id: u64, user: &Address, kind: BadgeKind) {
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        if !config.is_some_and(|config| config.badges.contains(kind)) {
+            return;
+        }
+
+        let mut badges = StorageHelper::get_badges(env, user);
+        if badges.iter().any(|badge| badge.quest_id == quest_id && badge.kind == kind) {
+            return;
+        }
+        badges.push_back(Badge { quest_id, kind, awarded_at: env.ledger().timestamp() });
+        env.storage().persistent().set(&DataKey::Badges(user.clone()), &badges);
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "badge_minted"),), BadgeMintedEvent {
+            quest_id,
+            user: user.clone(),
+            kind,
+        });
+    }
+
+    /// Credita a indicação de um usuário que acabou de se tornar elegível pela primeira vez
+    /// Paga o bônus de indicação da quest, se o pool tiver sobra, e avança as tarefas Referral do indicador
+    fn credit_referral(env: &Env, quest_id: u64, user: &Address) {
+        let referral: Option<Referral> = env.storage().persistent().get(&DataKey::Referrer(user.clone()));
+        let Some(mut referral) = referral else {
+            return;
+        };
+        if referral.credited {
+            return;
+        }
+        referral.credited = true;
+        env.storage().persistent().set(&DataKey::Referrer(user.clone()), &referral);
+
+        let referrer = referral.referrer;
+        let count: u32 = env.storage().persistent().get(&DataKey::Referrals(referrer.clone())).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::Referrals(referrer.clone()), &(count + 1));
+
+        // O bônus só sai do que sobra no pool além do necessário para os ganhadores
+        let mut quest: Quest = env.storage().persistent().get(&DataKey::Quests(quest_id)).unwrap();
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        let bonus = config.map(|config| config.referrer_bonus).unwrap_or(0);
+        let surplus = quest.total_reward_pool.saturating_sub(StorageHelper::required_reward_pool(env, &quest));
+        let bonus = if bonus <= surplus { bonus } else { 0 };
+        if bonus > 0 {
+            quest.total_reward_pool -= bonus;
+            env.storage().persistent().set(&DataKey::Quests(quest_id), &quest);
+            Self::pay_or_owe(env, quest_id, &quest.reward_token, &referrer, bonus);
+        }
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "referral_credited"),), ReferralCreditedEvent {
+            quest_id,
+            referrer: referrer.clone(),
+            user: user.clone(),
+            bonus,
+        });
+
+        if StorageHelper::is_denied(env, &referrer) {
+            return;
+        }
+
+        // Avança as tarefas Referral das quests ativas mais recentes em que o indicador está registrado,
+        // só nas do mesmo admin da quest em que o indicado ficou elegível, para que outro admin não forje indicações
+        let referrer_quests: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::UserQuests(referrer.clone()))
+            .unwrap_or(Vec::new(env));
+        for referral_quest_id in referrer_quests.iter().rev().take(MAX_REFERRAL_QUESTS as usize) {
+            let referral_quest: Quest = env.storage().persistent().get(&DataKey::Quests(referral_quest_id)).unwrap();
+            if !referral_quest.is_active
+                || env.ledger().timestamp() > referral_quest.end_timestamp
+                || referral_quest.distribution == DistributionType::Leaderboard
+                || referral_quest.admin != quest.admin
+            {
+                continue;
+            }
+
+            for (index, task) in StorageHelper::get_tasks(env, &referral_quest).iter().enumerate() {
+                if let QuestType::Referral(_) = task {
+                    let current = StorageHelper::get_progress_values(env, &referral_quest, &referrer)
+                        .get(index as u32)
+                        .unwrap();
+                    Self::update_task_progress(env, &referral_quest, &referrer, index as u32, current + 1);
+                }
+            }
+        }
+    }
+
+    /// Registra um usuário em uma quest, cobrando a taxa de inscrição de quem paga por ele
+    /// Com as vagas esgotadas, o usuário entra na fila de espera com a taxa em custódia
+    fn register_user(env: &Env, quest_id: u64, user: &Address, payer: &Address) {
+        // Verifica se a quest existe e está ativa
+        let quest: Quest = env.storage().persistent()
+            .get(&DataKey::Quests(quest_id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::QuestNotFound));
+        
+        if !quest.is_active {
+            panic_with_error!(env, Error::QuestNotActive);
+        }
+        
+        if env.ledger().timestamp() > quest.end_timestamp {
+            panic_with_error!(env, Error::QuestExpired);
+        }
+
+        if env.storage().persistent().has(&DataKey::RegistrationClosed(quest_id)) {
+            panic_with_error!(env, Error::RegistrationClosed);
+        }
+
+        StorageHelper::require_not_denied(env, user);
+        if !StorageHelper::is_allowlisted(env, quest_id, user) {
+            panic_with_error!(env, Error::NotAllowlisted);
+        }
+        StorageHelper::require_min_account_age(env, quest_id, user);
+        if !StorageHelper::has_met_prerequisites(env, quest_id, user) {
+            panic_with_error!(env, Error::PrerequisiteNotMet);
+        }
+
+        // Verifica se o usuário já está registrado ou na fila de espera
+        if env.storage().persistent().has(&DataKey::Registrations(quest_id, user.clone()))
+            || StorageHelper::get_waitlist(env, quest_id).contains(user)
+        {
+            panic_with_error!(env, Error::AlreadyRegistered);
+        }
+
+        StorageHelper::record_first_seen(env, user);
+
+        // Guarda quem pagou a taxa quando não foi o próprio usuário
+        let entry_fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest_id));
+        if payer != user && entry_fee.is_some() {
+            env.storage().persistent().set(&DataKey::EntryFeePayer(quest_id, user.clone()), payer);
+        }
+
+        if StorageHelper::is_registration_full(env, quest_id) {
+            if let Some(fee) = entry_fee {
+                Self::collect_funds(env, &quest.reward_token, payer, fee.amount);
+            }
+
+            let mut waitlist = StorageHelper::get_waitlist(env, quest_id);
+            waitlist.push_back(user.clone());
+            env.storage().persistent().set(&DataKey::Waitlist(quest_id), &waitlist);
+
+            // Emit event
+            env.events().publish((Symbol::new(env, "user_waitlisted"),), UserRegisteredEvent {
+                quest_id,
+                user: user.clone(),
+            });
+            return;
+        }
+
+        // Cobra a taxa de inscrição, se houver
+        Self::collect_entry_fee(env, &quest, payer);
+
+        Self::add_registration(env, quest_id, user);
+    }
+
+    /// Grava o registro do usuário e o adiciona às listas da quest
+    fn add_registration(env: &Env, quest_id: u64, user: &Address) {
+        env.storage().persistent().set(&DataKey::Registrations(quest_id, user.clone()), &true);
+
+        // Atualiza a lista de quests do usuário
+        let mut user_quests: Vec<u64> = env.storage().persistent()
+            .get(&DataKey::UserQuests(user.clone()))
+            .unwrap_or(Vec::new(env));
+        user_quests.push_back(quest_id);
+        env.storage().persistent().set(&DataKey::UserQuests(user.clone()), &user_quests);
+
+        // Atualiza a lista de registrados da quest
+        let mut registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
+            .unwrap_or(Vec::new(env));
+        registrants.push_back(user.clone());
+        env.storage().persistent().set(&DataKey::Registrants(quest_id), &registrants);
+
+        // O XP de registro só é concedido no primeiro registro do usuário na quest
+        let mut stats = StorageHelper::get_user_stats(env, user);
+        stats.total_participated += 1;
+        let xp_key = DataKey::RegistrationXp(quest_id, user.clone());
+        if !env.storage().persistent().has(&xp_key) {
+            env.storage().persistent().set(&xp_key, &true);
+            StorageHelper::add_xp(env, &mut stats, user, StorageHelper::get_xp_rewards(env, quest_id).registration);
+        }
+        StorageHelper::set_user_stats(env, user, stats);
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "user_registered"),), UserRegisteredEvent {
+            quest_id,
+            user: user.clone(),
+        });
+    }
+
+    /// Promove o primeiro da fila de espera para a vaga liberada
+    /// A taxa dele já está em custódia e só agora entra na contabilidade da quest
+    fn promote_from_waitlist(env: &Env, quest_id: u64) {
+        let quest: Quest = env.storage().persistent().get(&DataKey::Quests(quest_id)).unwrap();
+        let mut waitlist = StorageHelper::get_waitlist(env, quest_id);
+
+        // Quem deixou de cumprir as regras de registro enquanto esperava sai da fila com a taxa devolvida
+        while let Some(next) = waitlist.pop_front() {
+            if StorageHelper::is_denied(env, &next)
+                || !StorageHelper::is_allowlisted(env, quest_id, &next)
+                || !StorageHelper::has_met_prerequisites(env, quest_id, &next)
+            {
+                Self::refund_waitlisted_fee(env, &quest, &next);
+                continue;
+            }
+
+            env.storage().persistent().set(&DataKey::Waitlist(quest_id), &waitlist);
+            Self::apply_entry_fee(env, &quest, &StorageHelper::get_entry_fee_payer(env, quest_id, &next));
+            Self::add_registration(env, quest_id, &next);
+            return;
+        }
+
+        env.storage().persistent().set(&DataKey::Waitlist(quest_id), &waitlist);
+    }
+
+    /// Tira um usuário da fila de espera, devolvendo a taxa em custódia a quem pagou
+    /// Retorna o valor devolvido, ou None se o usuário não estava na fila
+    fn leave_waitlist(env: &Env, quest: &Quest, user: &Address) -> Option<u128> {
+        let mut waitlist = StorageHelper::get_waitlist(env, quest.id);
+        let index = waitlist.first_index_of(user)?;
+        waitlist.remove(index);
+        env.storage().persistent().set(&DataKey::Waitlist(quest.id), &waitlist);
+
+        Some(Self::refund_waitlisted_fee(env, quest, user))
+    }
+
+    /// Devolve a taxa de quem ainda está na fila quando a quest é resolvida ou cancelada
+    fn release_waitlist(env: &Env, quest: &Quest) {
+        for user in StorageHelper::get_waitlist(env, quest.id).iter() {
+            Self::refund_waitlisted_fee(env, quest, &user);
+        }
+        env.storage().persistent().remove(&DataKey::Waitlist(quest.id));
+    }
+
+    /// Devolve a quem pagou a taxa em custódia de um usuário da fila de espera
+    fn refund_waitlisted_fee(env: &Env, quest: &Quest, user: &Address) -> u128 {
+        let entry_fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest.id));
+        let Some(fee) = entry_fee else {
+            return 0;
+        };
+
+        let payer = StorageHelper::get_entry_fee_payer(env, quest.id, user);
+        env.storage().persistent().remove(&DataKey::EntryFeePayer(quest.id, user.clone()));
+
+        // Um pagador que não pode receber não impede a resolução: a taxa fica devida
+        Self::pay_or_owe(env, quest.id, &quest.reward_token, &payer, fee.amount);
+
+        fee.amount
+    }
+
+    /// Cobra a taxa de inscrição de uma quest: a parte do admin fica em custódia e o resto vai ao pool
+    fn collect_entry_fee(env: &Env, quest: &Quest, payer: &Address) {
+        let fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest.id));
+        let Some(fee) = fee else {
+            return;
+        };
+
+        Self::collect_funds(env, &quest.reward_token, payer, fee.amount);
+        Self::apply_entry_fee(env, quest, payer);
+    }
+
+    /// Contabiliza uma taxa de inscrição já recebida pelo contrato
+    fn apply_entry_fee(env: &Env, quest: &Quest, payer: &Address) {
+        let fee: Option<EntryFee> = env.storage().persistent().get(&DataKey::EntryFee(quest.id));
+        let Some(mut fee) = fee else {
+            return;
+        };
+
+        let cut = fee.amount * fee.admin_cut_bps as u128 / 10_000;
+        let pooled = fee.amount - cut;
+
+        fee.collected_cut += cut;
+        env.storage().persistent().set(&DataKey::EntryFee(quest.id), &fee);
+
+        if pooled > 0 {
+            let mut quest = quest.clone();
+            StorageHelper::add_contribution(env, quest.id, payer, pooled);
+            StorageHelper::apply_funding(env, &mut quest, pooled, fee.mode);
+            env.storage().persistent().set(&DataKey::Quests(quest.id), &quest);
+        }
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "entry_fee_paid"),), EntryFeePaidEvent {
+            quest_id: quest.id,
+            user: payer.clone(),
+            amount: fee.amount,
+        });
+    }
+
+    /// Cobra a taxa do protocolo sobre um aporte ao pool, quando a quest usa a base RewardPool
+    fn charge_pool_fee(env: &Env, quest_id: u64, token: &Address, payer: &Address, amount: u128) {
+        let quest_fee: Option<QuestFee> = env.storage().persistent().get(&DataKey::QuestFee(quest_id));
+        let Some(quest_fee) = quest_fee else {
+            return;
+        };
+        if quest_fee.fee_base != FeeBase::RewardPool {
+            return;
+        }
+
+        let fee = amount * quest_fee.fee_bps as u128 / 10_000;
+        if fee > 0 {
+            Self::collect_funds(env, token, payer, fee);
+            StorageHelper::accrue_protocol_fee(env, quest_id, token, fee);
+        }
+    }
+
+    /// Desconta a taxa do protocolo de um pagamento, quando a quest usa a base Payout
+    /// Retorna o valor líquido a ser transferido
+    fn take_payout_fee(env: &Env, quest: &Quest, amount: u128) -> u128 {
+        let quest_fee: Option<QuestFee> = env.storage().persistent().get(&DataKey::QuestFee(quest.id));
+        let Some(quest_fee) = quest_fee else {
+            return amount;
+        };
+        if quest_fee.fee_base != FeeBase::Payout {
+            return amount;
+        }
+
+        let fee = amount * quest_fee.fee_bps as u128 / 10_000;
+        if fee > 0 {
+            StorageHelper::accrue_protocol_fee(env, quest.id, &quest.reward_token, fee);
+        }
+
+        amount - fee
+    }
+
+    /// Paga um ganhador ou, se a transferência falhar, registra o valor como devido
+    /// Ganhadores na denylist não recebem, mas o valor fica devido caso sejam desbloqueados
+    fn pay_or_owe(env: &Env, quest_id: u64, token: &Address, recipient: &Address, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+
+        let reason = if StorageHelper::is_denied(env, recipient) {
+            PayoutFailureReason::Denied
+        } else if Self::try_pay(env, token, recipient, amount) {
+            return;
+        } else {
+            StorageHelper::payout_failure_reason(env, token, recipient)
+        };
+
+        StorageHelper::add_owed_reward(env, quest_id, recipient, token, amount);
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "payout_failed"),), PayoutFailedEvent {
+            quest_id,
+            recipient: recipient.clone(),
+            token: token.clone(),
+            amount,
+            reason,
+        });
+    }
+
+    /// Tenta transferir do contrato para o destinatário sem reverter a transação em caso de falha
+    fn try_pay(env: &Env, token: &Address, recipient: &Address, amount: u128) -> bool {
+        matches!(
+            token::Client::new(env, token).try_transfer(
+                &env.current_contract_address(),
+                recipient,
+                &(amount as i128)
+            ),
+            Ok(Ok(()))
+        )
+    }
+
+    /// Transfere fundos de um endereço para o contrato, verificando o saldo antes
+    fn collect_funds(env: &Env, token: &Address, from: &Address, amount: u128) {
+        let token_client = token::Client::new(env, token);
+
+        let balance = token_client.balance(from);
+        if balance < (amount as i128) {
+            panic_with_error!(env, Error::InsufficientBalance);
+        }
+
+        token_client.transfer(from, &env.current_contract_address(), &(amount as i128));
+    }
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct QuestStats {
@@ -1,5 +1,23 @@
-use soroban_sdk::{contracttype, Env, Address, Vec};
-use crate::{DataKey, Quest};
+use soroban_sdk::{contracttype, panic_with_error, token, xdr::ToXdr, Bytes, BytesN, Env, Address, Map, Symbol, Vec};
+use crate::{AllowlistMode, Badge, BonusReward, DataKey, DistributionType, Error, FundingMode, LeaderboardEntry, OwedReward, PayoutFailureReason, Prerequisite, PrerequisiteLevel, PrizeTier, ProtocolConfig, ProtocolFeeChargedEvent, Quest, QuestConfig, QuestType, SponsorContribution, StreamPosition, StreamState, TicketEntry, VestingSchedule, VestingStatus, WinnerPrize, XpRewards};
+
+/// Escala do acumulador de streaming, para não perder precisão na divisão por usuário
+pub const STREAM_PRECISION: u128 = 1_000_000_000_000;
+
+/// Quantidade de quests mais recentes do indicador em que cada indicação avança tarefas Referral
+pub const MAX_REFERRAL_QUESTS: u32 = 10;
+
+/// Taxa máxima do protocolo, em basis points (10%)
+pub const MAX_PROTOCOL_FEE_BPS: u32 = 1_000;
+
+/// Quantidade de usuários mantidos no ranking de XP de cada temporada
+pub const TOP_USERS_SIZE: u32 = 10;
+
+/// XP máximo que uma quest pode conceder em cada etapa (registro, conclusão e vitória)
+pub const MAX_XP_REWARD: u128 = 1_000;
+
+/// Quantidade máxima de usuários pontuados em uma quest Leaderboard
+pub const MAX_LEADERBOARD_ENTRIES: u32 = 200;
 
 // Funções utilitárias para gerenciamento de storage
 pub struct StorageHelper;
@@ -28,6 +46,542 @@ impl StorageHelper {
         }
     }
 
+    /// Registra um aporte de um patrocinador no pool de uma quest
+    pub fn add_contribution(env: &Env, quest_id: u64, sponsor: &Address, amount: u128) {
+        let key = DataKey::Contributions(quest_id, sponsor.clone());
+        let current: u128 = env.storage().persistent().get(&key).unwrap_or(0);
+
+        if current == 0 {
+            let mut sponsors: Vec<Address> = env.storage().persistent()
+                .get(&DataKey::Sponsors(quest_id))
+                .unwrap_or(Vec::new(env));
+            sponsors.push_back(sponsor.clone());
+            env.storage().persistent().set(&DataKey::Sponsors(quest_id), &sponsors);
+        }
+
+        env.storage().persistent().set(&key, &(current + amount));
+    }
+
+    /// Desconta um valor do aporte de um patrocinador, removendo-o da lista quando zerar
+    pub fn remove_contribution(env: &Env, quest_id: u64, sponsor: &Address, amount: u128) {
+        let key = DataKey::Contributions(quest_id, sponsor.clone());
+        let current: u128 = env.storage().persistent().get(&key).unwrap_or(0);
+        let remaining = current.saturating_sub(amount);
+
+        if remaining > 0 {
+            env.storage().persistent().set(&key, &remaining);
+            return;
+        }
+
+        env.storage().persistent().remove(&key);
+        let mut sponsors: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Sponsors(quest_id))
+            .unwrap_or(Vec::new(env));
+        if let Some(index) = sponsors.first_index_of(sponsor) {
+            sponsors.remove(index);
+            env.storage().persistent().set(&DataKey::Sponsors(quest_id), &sponsors);
+        }
+    }
+
+    /// Obtém quem pagou a taxa de inscrição de um usuário (o relayer, em registros delegados)
+    pub fn get_entry_fee_payer(env: &Env, quest_id: u64, user: &Address) -> Address {
+        env.storage().persistent()
+            .get(&DataKey::EntryFeePayer(quest_id, user.clone()))
+            .unwrap_or(user.clone())
+    }
+
+    /// Obtém as contribuições de todos os patrocinadores de uma quest, na ordem do primeiro aporte
+    pub fn get_sponsor_contributions(env: &Env, quest_id: u64) -> Vec<SponsorContribution> {
+        let sponsors: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Sponsors(quest_id))
+            .unwrap_or(Vec::new(env));
+
+        let mut contributions = Vec::new(env);
+        for sponsor in sponsors.iter() {
+            let amount: u128 = env.storage().persistent()
+                .get(&DataKey::Contributions(quest_id, sponsor.clone()))
+                .unwrap_or(0);
+            contributions.push_back(SponsorContribution { sponsor, amount });
+        }
+
+        contributions
+    }
+
+    /// Obtém a configuração do protocolo
+    pub fn get_protocol_config(env: &Env) -> ProtocolConfig {
+        env.storage().instance()
+            .get(&DataKey::ProtocolConfig)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
+
+    /// Soma uma taxa ao saldo do protocolo, mantido separado dos pools das quests
+    pub fn accrue_protocol_fee(env: &Env, quest_id: u64, token: &Address, amount: u128) {
+        let key = DataKey::ProtocolFees(token.clone());
+        let accrued: u128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(accrued + amount));
+
+        // Emit event
+        env.events().publish((Symbol::new(env, "protocol_fee_charged"),), ProtocolFeeChargedEvent {
+            quest_id,
+            token: token.clone(),
+            amount,
+        });
+    }
+
+    /// Verifica se o modo de financiamento é compatível com a quest
+    pub fn validate_funding_mode(env: &Env, quest: &Quest, mode: FundingMode) {
+        if mode == FundingMode::PoolOnly {
+            return;
+        }
+        // A tabela de prêmios fixa ganhadores e valores, então só aceita aportes ao pool
+        if env.storage().persistent().has(&DataKey::PrizeTable(quest.id)) {
+            panic_with_error!(env, Error::InvalidPrizeTable);
+        }
+        if quest.distribution == DistributionType::Streaming {
+            panic_with_error!(env, Error::InvalidDistribution);
+        }
+    }
+
+    /// Soma um aporte ao pool e ajusta max_winners ou reward_per_winner conforme o modo
+    pub fn apply_funding(env: &Env, quest: &mut Quest, amount: u128, mode: FundingMode) {
+        quest.total_reward_pool += amount;
+
+        match mode {
+            FundingMode::PoolOnly => {},
+            FundingMode::IncreaseWinners => {
+                // Limitado também pelo que os pools dos tokens adicionais cobrem
+                let mut covered_winners = quest.total_reward_pool / quest.reward_per_winner;
+                for bonus in Self::get_bonus_rewards(env, quest.id).iter() {
+                    covered_winners = covered_winners.min(bonus.total_pool / bonus.amount_per_winner);
+                }
+                if covered_winners > quest.max_winners as u128 {
+                    quest.max_winners = covered_winners.min(u32::MAX as u128) as u32;
+                }
+            },
+            FundingMode::IncreaseReward => {
+                let covered_reward = quest.total_reward_pool / quest.max_winners as u128;
+                if covered_reward > quest.reward_per_winner {
+                    quest.reward_per_winner = covered_reward;
+                }
+            },
+        }
+    }
+
+    /// Retira um valor do pool e reduz max_winners ou reward_per_winner ao que o pool ainda cobre
+    /// Desfaz apply_funding sem ficar abaixo do que o admin financiou originalmente
+    pub fn revert_funding(env: &Env, quest: &mut Quest, amount: u128, mode: FundingMode) {
+        quest.total_reward_pool -= amount;
+
+        match mode {
+            FundingMode::PoolOnly => {},
+            FundingMode::IncreaseWinners => {
+                let mut covered_winners = quest.total_reward_pool / quest.reward_per_winner;
+                for bonus in Self::get_bonus_rewards(env, quest.id).iter() {
+                    covered_winners = covered_winners.min(bonus.total_pool / bonus.amount_per_winner);
+                }
+                if covered_winners < quest.max_winners as u128 {
+                    quest.max_winners = covered_winners as u32;
+                }
+            },
+            FundingMode::IncreaseReward => {
+                let covered_reward = quest.total_reward_pool / quest.max_winners as u128;
+                if covered_reward < quest.reward_per_winner {
+                    quest.reward_per_winner = covered_reward;
+                }
+            },
+        }
+    }
+
+    /// Obtém os tokens adicionais do pacote de recompensas de uma quest
+    pub fn get_bonus_rewards(env: &Env, quest_id: u64) -> Vec<BonusReward> {
+        env.storage().persistent()
+            .get(&DataKey::BonusRewards(quest_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Identifica por que uma transferência para o destinatário falhou
+    /// Em SACs de ativos clássicos, contas sem trustline (ou sem conta, no XLM nativo) falham no saldo
+    /// e endereços desautorizados pelo emissor não podem receber; o resto é rejeição do próprio token
+    pub fn payout_failure_reason(env: &Env, token: &Address, recipient: &Address) -> PayoutFailureReason {
+        if token::Client::new(env, token).try_balance(recipient).is_err() {
+            return PayoutFailureReason::NoTrustline;
+        }
+
+        match token::StellarAssetClient::new(env, token).try_authorized(recipient) {
+            Ok(Ok(false)) => PayoutFailureReason::Unauthorized,
+            _ => PayoutFailureReason::TransferRejected,
+        }
+    }
+
+    /// Obtém os pagamentos pendentes de um ganhador em uma quest
+    pub fn get_owed_rewards(env: &Env, quest_id: u64, user: &Address) -> Vec<OwedReward> {
+        env.storage().persistent()
+            .get(&DataKey::OwedRewards(quest_id, user.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Soma um pagamento pendente ao saldo devido de um ganhador
+    pub fn add_owed_reward(env: &Env, quest_id: u64, user: &Address, token: &Address, amount: u128) {
+        let mut owed = Self::get_owed_rewards(env, quest_id, user);
+
+        match owed.iter().position(|entry| entry.token == *token) {
+            Some(index) => {
+                let mut entry = owed.get(index as u32).unwrap();
+                entry.amount += amount;
+                owed.set(index as u32, entry);
+            }
+            None => owed.push_back(OwedReward { token: token.clone(), amount }),
+        }
+
+        env.storage().persistent().set(&DataKey::OwedRewards(quest_id, user.clone()), &owed);
+    }
+
+    /// Calcula o valor necessário no pool para pagar todos os ganhadores possíveis
+    pub fn required_reward_pool(env: &Env, quest: &Quest) -> u128 {
+        // Em Streaming o pool cobre a emissão por segundo durante toda a quest
+        if quest.distribution == DistributionType::Streaming {
+            let state: StreamState = env.storage().persistent()
+                .get(&DataKey::Stream(quest.id))
+                .unwrap();
+            return quest.reward_per_winner.saturating_mul((quest.end_timestamp - state.start_timestamp) as u128);
+        }
+
+        let prize_table: Option<Vec<PrizeTier>> = env.storage().persistent()
+            .get(&DataKey::PrizeTable(quest.id));
+
+        match prize_table {
+            Some(tiers) => {
+                let mut previous_rank = 0u32;
+                let mut total = 0u128;
+                for tier in tiers.iter() {
+                    total += (tier.last_rank - previous_rank) as u128 * tier.amount;
+                    previous_rank = tier.last_rank;
+                }
+                total
+            },
+            // Em ProRata o pool é dividido, então basta cobrir o limite por usuário
+            None if quest.distribution == DistributionType::ProRata => quest.reward_per_winner,
+            None => quest.reward_per_winner * quest.max_winners as u128,
+        }
+    }
+
+    /// Calcula o prêmio de cada ganhador pela ordem da lista de ganhadores (posição 1 = primeiro)
+    pub fn get_winner_prizes(env: &Env, quest: &Quest) -> Vec<WinnerPrize> {
+        // Quests ProRata guardam os valores calculados na resolução
+        if let Some(allocations) = env.storage().persistent().get(&DataKey::Allocations(quest.id)) {
+            return allocations;
+        }
+
+        let winners: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Winners(quest.id))
+            .unwrap_or(Vec::new(env));
+        let prize_table: Vec<PrizeTier> = env.storage().persistent()
+            .get(&DataKey::PrizeTable(quest.id))
+            .unwrap_or(Vec::new(env));
+
+        let mut prizes = Vec::new(env);
+        for (index, winner) in winners.iter().enumerate() {
+            let rank = index as u32 + 1;
+            let amount = if prize_table.is_empty() {
+                quest.reward_per_winner
+            } else {
+                prize_table.iter()
+                    .find(|tier| rank <= tier.last_rank)
+                    .map(|tier| tier.amount)
+                    .unwrap_or(0)
+            };
+            prizes.push_back(WinnerPrize { winner, rank, amount });
+        }
+
+        prizes
+    }
+
+    /// Gera a próxima semente pseudo-aleatória dos sorteios
+    pub fn next_seed(seed: u64) -> u64 {
+        (seed.wrapping_mul(1103515245).wrapping_add(12345)) / 65536
+    }
+
+    /// Obtém os bilhetes de cada participante elegível de um sorteio ponderado
+    /// Participantes sem bilhetes atribuídos concorrem com 1 bilhete
+    pub fn get_ticket_entries(env: &Env, quest_id: u64) -> Vec<TicketEntry> {
+        let participants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Participants(quest_id))
+            .unwrap_or(Vec::new(env));
+        let ticket_table: Map<Address, u32> = env.storage().persistent()
+            .get(&DataKey::Tickets(quest_id))
+            .unwrap_or(Map::new(env));
+
+        let mut entries = Vec::new(env);
+        for user in participants.iter() {
+            let tickets = ticket_table.get(user.clone()).unwrap_or(1);
+            entries.push_back(TicketEntry { user, tickets });
+        }
+
+        entries
+    }
+
+    /// Obtém o ranking de uma quest Leaderboard, limitado aos max_winners atuais
+    /// Em caso de empate, quem atingiu a pontuação primeiro fica à frente
+    pub fn get_leaderboard(env: &Env, quest: &Quest) -> Vec<LeaderboardEntry> {
+        let scores: Map<Address, u128> = env.storage().persistent()
+            .get(&DataKey::Scores(quest.id))
+            .unwrap_or(Map::new(env));
+        let participants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Participants(quest.id))
+            .unwrap_or(Vec::new(env));
+
+        // Pontuação zero não classifica
+        let mut candidates = Vec::new(env);
+        for user in participants.iter() {
+            if scores.get(user.clone()).unwrap_or(0) > 0 {
+                candidates.push_back(user);
+            }
+        }
+
+        let mut leaderboard = Vec::new(env);
+        for user in Self::rank_by_score(env, &candidates, &scores, quest.max_winners).iter() {
+            let score = scores.get(user.clone()).unwrap_or(0);
+            leaderboard.push_back(LeaderboardEntry { user, score });
+        }
+
+        leaderboard
+    }
+
+    /// Reposiciona a pontuação de um usuário em um ranking ordenado, mantendo apenas as `size` melhores
+    fn rank_entry(leaderboard: &mut Vec<LeaderboardEntry>, user: &Address, score: u128, size: u32) {
+        if let Some(index) = leaderboard.iter().position(|entry| entry.user == *user) {
+            leaderboard.remove(index as u32);
+        }
+
+        // Insere depois de todos com pontuação maior ou igual
+        let position = leaderboard.iter()
+            .position(|entry| entry.score < score)
+            .map(|index| index as u32)
+            .unwrap_or(leaderboard.len());
+
+        if position < size {
+            leaderboard.insert(position, LeaderboardEntry { user: user.clone(), score });
+            while leaderboard.len() > size {
+                leaderboard.pop_back();
+            }
+        }
+    }
+
+    /// Ordena candidatos por pontuação decrescente, mantendo a ordem original em caso de empate
+    pub fn rank_by_score(env: &Env, candidates: &Vec<Address>, scores: &Map<Address, u128>, limit: u32) -> Vec<Address> {
+        let mut remaining = candidates.clone();
+        let mut ranked = Vec::new(env);
+
+        while ranked.len() < limit && !remaining.is_empty() {
+            let mut best_index = 0u32;
+            let mut best_score = scores.get(remaining.get(0).unwrap()).unwrap_or(0);
+
+            for (index, candidate) in remaining.iter().enumerate().skip(1) {
+                let score = scores.get(candidate).unwrap_or(0);
+                if score > best_score {
+                    best_index = index as u32;
+                    best_score = score;
+                }
+            }
+
+            ranked.push_back(remaining.get(best_index).unwrap());
+            remaining.remove(best_index);
+        }
+
+        ranked
+    }
+
+    /// Divide o pool de uma quest ProRata proporcionalmente às pontuações
+    /// Cada usuário recebe no máximo reward_per_winner; o excedente é redistribuído entre os demais
+    /// A poeira do arredondamento vai para os melhores colocados, respeitando o limite
+    pub fn allocate_pro_rata(env: &Env, quest: &Quest) -> Vec<WinnerPrize> {
+        let scores: Map<Address, u128> = env.storage().persistent()
+            .get(&DataKey::Scores(quest.id))
+            .unwrap_or(Map::new(env));
+        let participants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Participants(quest.id))
+            .unwrap_or(Vec::new(env));
+
+        let mut candidates = Vec::new(env);
+        for participant in participants.iter() {
+            if scores.get(participant.clone()).unwrap_or(0) > 0 {
+                candidates.push_back(participant);
+            }
+        }
+
+        let ranked = Self::rank_by_score(env, &candidates, &scores, quest.max_winners);
+        let cap = quest.reward_per_winner;
+
+        let mut amounts: Vec<u128> = Vec::new(env);
+        let mut capped: Vec<bool> = Vec::new(env);
+        for _ in ranked.iter() {
+            amounts.push_back(0);
+            capped.push_back(false);
+        }
+
+        // Rodadas até nenhum usuário ultrapassar o limite
+        let mut remaining_pool = quest.total_reward_pool;
+        loop {
+            let mut open_score = 0u128;
+            for (index, user) in ranked.iter().enumerate() {
+                if !capped.get(index as u32).unwrap() {
+                    open_score = open_score.checked_add(scores.get(user).unwrap_or(0))
+                        .unwrap_or_else(|| panic_with_error!(env, Error::ArithmeticOverflow));
+                }
+            }
+            if open_score == 0 {
+                break;
+            }
+
+            let mut newly_capped = false;
+            for (index, user) in ranked.iter().enumerate() {
+                let index = index as u32;
+                if capped.get(index).unwrap() {
+                    continue;
+                }
+                let share = Self::mul_div(env, remaining_pool, scores.get(user).unwrap_or(0), open_score);
+                if share >= cap {
+                    amounts.set(index, cap);
+                    capped.set(index, true);
+                    remaining_pool = remaining_pool.checked_sub(cap)
+                        .unwrap_or_else(|| panic_with_error!(env, Error::ArithmeticOverflow));
+                    newly_capped = true;
+                }
+            }
+
+            if !newly_capped {
+                for (index, user) in ranked.iter().enumerate() {
+                    let index = index as u32;
+                    if !capped.get(index).unwrap() {
+                        amounts.set(index, Self::mul_div(env, remaining_pool, scores.get(user).unwrap_or(0), open_score));
+                    }
+                }
+                break;
+            }
+        }
+
+        // Distribui a poeira do arredondamento por ordem de pontuação
+        let allocated: u128 = amounts.iter().sum();
+        let mut dust = quest.total_reward_pool.saturating_sub(allocated);
+        for index in 0..amounts.len() {
+            if dust == 0 {
+                break;
+            }
+            let amount = amounts.get(index).unwrap();
+            let extra = dust.min(cap - amount);
+            amounts.set(index, amount + extra);
+            dust -= extra;
+        }
+
+        let mut allocations = Vec::new(env);
+        let mut total_allocated = 0u128;
+        for (index, winner) in ranked.iter().enumerate() {
+            let amount = amounts.get(index as u32).unwrap();
+            if amount > 0 {
+                total_allocated += amount;
+                allocations.push_back(WinnerPrize {
+                    winner,
+                    rank: allocations.len() + 1,
+                    amount,
+                });
+            }
+        }
+
+        // Nunca aloca mais do que o pool da própria quest
+        if total_allocated > quest.total_reward_pool {
+            panic_with_error!(env, Error::InsufficientRewardPool);
+        }
+
+        allocations
+    }
+
+    /// Calcula a * b / c, abortando em caso de overflow
+    pub fn mul_div(env: &Env, a: u128, b: u128, c: u128) -> u128 {
+        a.checked_mul(b)
+            .unwrap_or_else(|| panic_with_error!(env, Error::ArithmeticOverflow))
+            / c
+    }
+
+    /// Calcula quanto da recompensa de um ganhador já foi liberado e resgatado
+    pub fn get_vesting_status(env: &Env, quest: &Quest, user: &Address) -> VestingStatus {
+        let schedule: VestingSchedule = env.storage().persistent()
+            .get(&DataKey::Vesting(quest.id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::VestingNotConfigured));
+
+        // O vesting só começa quando a quest é resolvida
+        let start_timestamp = schedule.start_timestamp
+            .unwrap_or_else(|| panic_with_error!(env, Error::QuestNotResolved));
+
+        let total: u128 = Self::get_winner_prizes(env, quest).iter()
+            .filter(|prize| prize.winner == *user)
+            .map(|prize| prize.amount)
+            .sum();
+        let claimed: u128 = env.storage().persistent()
+            .get(&DataKey::VestingClaimed(quest.id, user.clone()))
+            .unwrap_or(0);
+
+        let elapsed = env.ledger().timestamp().saturating_sub(start_timestamp);
+        let vested = if elapsed < schedule.cliff_seconds {
+            0
+        } else if elapsed >= schedule.duration_seconds {
+            total
+        } else {
+            total * elapsed as u128 / schedule.duration_seconds as u128
+        };
+
+        VestingStatus {
+            total,
+            vested,
+            claimed,
+            remaining: total - claimed,
+        }
+    }
+
+    /// Calcula o acumulador de streaming até agora (ou até o fim da quest) sem gravar
+    pub fn preview_stream(env: &Env, quest: &Quest) -> StreamState {
+        let mut state: StreamState = env.storage().persistent()
+            .get(&DataKey::Stream(quest.id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::InvalidDistribution));
+
+        // Quests resolvidas ou canceladas não emitem mais
+        if !quest.is_active {
+            return state;
+        }
+
+        let until = env.ledger().timestamp().min(quest.end_timestamp);
+        if until > state.last_update {
+            let emitted = quest.reward_per_winner * (until - state.last_update) as u128;
+            if state.eligible_count > 0 {
+                state.acc_reward_per_user += emitted * STREAM_PRECISION / state.eligible_count as u128;
+                state.distributed += emitted;
+            } else {
+                state.undistributed += emitted;
+            }
+            state.last_update = until;
+        }
+
+        state
+    }
+
+    /// Atualiza e grava o acumulador de streaming de uma quest
+    pub fn update_stream(env: &Env, quest: &Quest) -> StreamState {
+        let state = Self::preview_stream(env, quest);
+        env.storage().persistent().set(&DataKey::Stream(quest.id), &state);
+        state
+    }
+
+    /// Obtém a posição de um usuário no streaming de uma quest
+    pub fn get_stream_position(env: &Env, quest_id: u64, user: &Address) -> StreamPosition {
+        env.storage().persistent()
+            .get(&DataKey::StreamPositions(quest_id, user.clone()))
+            .unwrap_or(StreamPosition {
+                is_eligible: false,
+                acc_snapshot: 0,
+                pending: 0,
+                claimed: 0,
+            })
+    }
+
     /// Obtém quests por status
     pub fn get_quests_by_status(env: &Env, active_only: bool) -> Vec<Quest> {
         let quest_ids = Self::get_all_quest_ids(env);
@@ -38,9 +592,7 @@ impl StorageHelper {
             if let Some(quest) = env.storage().persistent().get::<DataKey, Quest>(&DataKey::Quests(quest_id)) {
                 let is_active = quest.is_active && current_time <= quest.end_timestamp;
                 
-                if active_only && is_active {
-                    filtered_quests.push_back(quest);
-                } else if !active_only && !is_active {
+                if active_only == is_active {
                     filtered_quests.push_back(quest);
                 }
             }
@@ -62,50 +614,342 @@ impl StorageHelper {
         true
     }
 
-    /// Obtém estatísticas de participação de um usuário
-    pub fn get_user_participation_stats(env: &Env, user: &Address) -> UserStats {
-        let user_quests: Vec<u64> = env.storage().persistent()
-            .get(&DataKey::UserQuests(user.clone()))
+    /// Obtém a fila de espera de uma quest
+    pub fn get_waitlist(env: &Env, quest_id: u64) -> Vec<Address> {
+        env.storage().persistent()
+            .get(&DataKey::Waitlist(quest_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Verifica se a quest já atingiu max_participants
+    pub fn is_registration_full(env: &Env, quest_id: u64) -> bool {
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        let max_participants = config.map(|config| config.max_participants).unwrap_or(0);
+        if max_participants == 0 {
+            return false;
+        }
+
+        let registrants: Vec<Address> = env.storage().persistent()
+            .get(&DataKey::Registrants(quest_id))
             .unwrap_or(Vec::new(env));
+        registrants.len() >= max_participants
+    }
 
-        let mut total_participated = 0u32;
-        let mut total_won = 0u32;
-        let mut total_rewards = 0u128;
+    /// Quantas entradas um usuário pode acumular na quest (1 sem múltiplas entradas)
+    pub fn max_entries_per_user(env: &Env, quest_id: u64) -> u32 {
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        match config {
+            Some(config) if config.allow_multiple_entries => config.max_entries_per_user,
+            _ => 1,
+        }
+    }
 
-        for quest_id in user_quests.iter() {
-            total_participated += 1;
+    /// Conta uma nova entrada do usuário, se ele ainda não atingiu o limite
+    pub fn try_add_entry(env: &Env, quest_id: u64, user: &Address) -> bool {
+        let key = DataKey::Entries(quest_id, user.clone());
+        let entries: u32 = env.storage().persistent().get(&key).unwrap_or(0);
+        if entries >= Self::max_entries_per_user(env, quest_id) {
+            return false;
+        }
 
-            let winners: Vec<Address> = env.storage().persistent()
-                .get(&DataKey::Winners(quest_id))
-                .unwrap_or(Vec::new(env));
+        env.storage().persistent().set(&key, &(entries + 1));
+        true
+    }
 
-            if winners.contains(user) {
-                total_won += 1;
-                
-                if let Some(quest) = env.storage().persistent().get::<DataKey, Quest>(&DataKey::Quests(quest_id)) {
-                    total_rewards += quest.reward_per_winner;
+    /// Registra a primeira interação de um endereço com o contrato, se ainda não houver registro
+    pub fn record_first_seen(env: &Env, user: &Address) {
+        let key = DataKey::FirstSeen(user.clone());
+        if !env.storage().persistent().has(&key) {
+            env.storage().persistent().set(&key, &env.ledger().timestamp());
+        }
+    }
+
+    /// Exige que o endereço tenha sido visto há pelo menos min_account_age da QuestConfig
+    pub fn require_min_account_age(env: &Env, quest_id: u64, user: &Address) {
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        let min_account_age = config.map(|config| config.min_account_age).unwrap_or(0);
+        if min_account_age == 0 {
+            return;
+        }
+
+        let first_seen: Option<u64> = env.storage().persistent().get(&DataKey::FirstSeen(user.clone()));
+        match first_seen {
+            Some(first_seen) if env.ledger().timestamp() - first_seen >= min_account_age => {},
+            _ => panic_with_error!(env, Error::AccountTooNew),
+        }
+    }
+
+    /// Verifica se um endereço está na denylist global
+    pub fn is_denied(env: &Env, user: &Address) -> bool {
+        env.storage().persistent().has(&DataKey::Denied(user.clone()))
+    }
+
+    /// Impede que endereços da denylist avancem em quests ou recebam pagamentos
+    pub fn require_not_denied(env: &Env, user: &Address) {
+        if Self::is_denied(env, user) {
+            panic_with_error!(env, Error::UserDenied);
+        }
+    }
+
+    /// Verifica se o usuário pode se registrar na quest, considerando a allowlist
+    /// Na allowlist por Merkle, o usuário fica liberado depois de apresentar a prova
+    pub fn is_allowlisted(env: &Env, quest_id: u64, user: &Address) -> bool {
+        let mode: Option<AllowlistMode> = env.storage().persistent().get(&DataKey::Allowlist(quest_id));
+        mode.is_none() || env.storage().persistent().has(&DataKey::Allowed(quest_id, user.clone()))
+    }
+
+    /// Verifica a prova de Merkle de um endereço
+    /// A folha é o sha256 do XDR do endereço e cada par de nós é ordenado antes do hash
+    pub fn verify_merkle_proof(env: &Env, root: &BytesN<32>, user: &Address, proof: &Vec<BytesN<32>>) -> bool {
+        let mut node = env.crypto().sha256(&user.clone().to_xdr(env)).to_bytes();
+
+        for sibling in proof.iter() {
+            let (first, second) = if node < sibling { (node, sibling) } else { (sibling, node) };
+            let mut data = Bytes::new(env);
+            data.extend_from_array(&first.to_array());
+            data.extend_from_array(&second.to_array());
+            node = env.crypto().sha256(&data).to_bytes();
+        }
+
+        node == *root
+    }
+
+    /// Obtém as tarefas da quest: as sub-tarefas de uma quest composta ou a própria tarefa
+    pub fn get_tasks(env: &Env, quest: &Quest) -> Vec<QuestType> {
+        match &quest.quest_type {
+            QuestType::Composite(tasks, _) => tasks.clone(),
+            task => Vec::from_array(env, [task.clone()]),
+        }
+    }
+
+    /// Verifica se a tarefa, ou alguma sub-tarefa, depende de indicações
+    pub fn has_referral_task(quest_type: &QuestType) -> bool {
+        match quest_type {
+            QuestType::Referral(_) => true,
+            QuestType::Composite(tasks, _) => tasks.iter().any(|task| matches!(task, QuestType::Referral(_))),
+            _ => false,
+        }
+    }
+
+    /// Meta numérica de uma tarefa simples
+    pub fn task_target(task: &QuestType) -> u128 {
+        match task {
+            QuestType::TradeVolume(target) | QuestType::PoolPosition(target) | QuestType::TokenHold(_, target) => *target,
+            QuestType::Referral(count) => *count as u128,
+            QuestType::Composite(..) => 0,
+        }
+    }
+
+    /// Obtém o progresso reportado de um usuário em cada tarefa da quest
+    pub fn get_progress_values(env: &Env, quest: &Quest, user: &Address) -> Vec<u128> {
+        env.storage().persistent()
+            .get(&DataKey::Progress(quest.id, user.clone()))
+            .unwrap_or_else(|| {
+                let mut values = Vec::new(env);
+                for _ in 0..Self::get_tasks(env, quest).len() {
+                    values.push_back(0);
                 }
+                values
+            })
+    }
+
+    /// Obtém as sub-tarefas concluídas por um usuário (vazio se a quest não for composta)
+    pub fn get_task_completions(env: &Env, quest: &Quest, user: &Address) -> Vec<bool> {
+        let QuestType::Composite(tasks, _) = &quest.quest_type else {
+            return Vec::new(env);
+        };
+
+        env.storage().persistent()
+            .get(&DataKey::TaskCompletions(quest.id, user.clone()))
+            .unwrap_or_else(|| {
+                let mut completions = Vec::new(env);
+                for _ in 0..tasks.len() {
+                    completions.push_back(false);
+                }
+                completions
+            })
+    }
+
+    /// Verifica se o usuário concluiu o mínimo de sub-tarefas exigido (sempre verdadeiro em quests simples)
+    pub fn has_completed_tasks(env: &Env, quest: &Quest, user: &Address) -> bool {
+        let QuestType::Composite(_, required) = &quest.quest_type else {
+            return true;
+        };
+
+        let completed = Self::get_task_completions(env, quest, user).iter().filter(|done| *done).count();
+        completed as u32 >= *required
+    }
+
+    /// Obtém as quests que precisam ser cumpridas antes do registro em uma quest
+    pub fn get_prerequisites(env: &Env, quest_id: u64) -> Vec<Prerequisite> {
+        env.storage().persistent()
+            .get(&DataKey::Prerequisites(quest_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Verifica se o usuário cumpriu todos os pré-requisitos de uma quest
+    pub fn has_met_prerequisites(env: &Env, quest_id: u64, user: &Address) -> bool {
+        Self::get_prerequisites(env, quest_id).iter().all(|prerequisite| {
+            let Some(quest) = env.storage().persistent().get::<_, Quest>(&DataKey::Quests(prerequisite.quest_id)) else {
+                return false;
+            };
+
+            match prerequisite.level {
+                PrerequisiteLevel::Registered => {
+                    env.storage().persistent().has(&DataKey::Registrations(quest.id, user.clone()))
+                },
+                PrerequisiteLevel::Eligible => Self::has_qualified(env, &quest, user),
+                PrerequisiteLevel::Winner => {
+                    let winners: Vec<Address> = env.storage().persistent()
+                        .get(&DataKey::Winners(quest.id))
+                        .unwrap_or(Vec::new(env));
+                    winners.contains(user)
+                },
+                PrerequisiteLevel::Badge => Self::has_badge(env, user, quest.id),
+            }
+        })
+    }
+
+    /// Obtém os badges de um usuário
+    pub fn get_badges(env: &Env, user: &Address) -> Vec<Badge> {
+        env.storage().persistent()
+            .get(&DataKey::Badges(user.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Verifica se o usuário já recebeu algum badge de uma quest
+    pub fn has_badge(env: &Env, user: &Address, quest_id: u64) -> bool {
+        Self::get_badges(env, user).iter().any(|badge| badge.quest_id == quest_id)
+    }
+
+    /// Obtém a cadeia de uma quest: todas as quests anteriores das quais ela depende, direta ou
+    /// indiretamente, em ordem de execução (pré-requisitos têm IDs menores) e terminando nela mesma
+    pub fn get_quest_chain(env: &Env, quest_id: u64) -> Vec<u64> {
+        let mut chain: Vec<u64> = Vec::new(env);
+        let mut pending: Vec<u64> = Vec::new(env);
+        pending.push_back(quest_id);
+
+        while let Some(current) = pending.pop_back() {
+            if chain.contains(current) {
+                continue;
+            }
+
+            // Insere mantendo a ordem crescente de IDs
+            let position = chain.iter().position(|id| id > current).unwrap_or(chain.len() as usize);
+            chain.insert(position as u32, current);
+
+            for prerequisite in Self::get_prerequisites(env, current).iter() {
+                pending.push_back(prerequisite.quest_id);
             }
         }
 
-        UserStats {
-            total_participated,
-            total_won,
-            total_rewards,
-            win_rate: if total_participated > 0 {
-                (total_won as u128 * 10000) / total_participated as u128 // Percentual * 100 para evitar decimais
-            } else {
-                0
+        chain
+    }
+
+    /// Verifica se o usuário já avançou na quest (elegível, ganhador, pontuado ou acumulando no streaming)
+    pub fn has_qualified(env: &Env, quest: &Quest, user: &Address) -> bool {
+        match quest.distribution {
+            DistributionType::Fcfs => {
+                let winners: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Winners(quest.id))
+                    .unwrap_or(Vec::new(env));
+                winners.contains(user)
+            },
+            DistributionType::Raffle | DistributionType::ProRata | DistributionType::WeightedRaffle | DistributionType::Leaderboard => {
+                let participants: Vec<Address> = env.storage().persistent()
+                    .get(&DataKey::Participants(quest.id))
+                    .unwrap_or(Vec::new(env));
+                participants.contains(user)
+            },
+            DistributionType::Streaming => {
+                let position = Self::get_stream_position(env, quest.id, user);
+                position.is_eligible || position.pending > 0 || position.claimed > 0
             },
         }
     }
+
+    /// Obtém as estatísticas acumuladas de um usuário
+    pub fn get_user_stats(env: &Env, user: &Address) -> UserStats {
+        env.storage().persistent()
+            .get(&DataKey::UserStats(user.clone()))
+            .unwrap_or(UserStats {
+                total_participated: 0,
+                total_completed: 0,
+                total_won: 0,
+                total_rewards: 0,
+                win_rate: 0,
+                xp: 0,
+            })
+    }
+
+    /// Salva as estatísticas de um usuário, recalculando a taxa de vitórias
+    pub fn set_user_stats(env: &Env, user: &Address, mut stats: UserStats) {
+        stats.win_rate = if stats.total_participated > 0 {
+            (stats.total_won as u128 * 10000) / stats.total_participated as u128 // Percentual * 100 para evitar decimais
+        } else {
+            0
+        };
+        env.storage().persistent().set(&DataKey::UserStats(user.clone()), &stats);
+    }
+
+    /// Obtém o XP que uma quest concede em cada etapa
+    pub fn get_xp_rewards(env: &Env, quest_id: u64) -> XpRewards {
+        let config: Option<QuestConfig> = env.storage().persistent().get(&DataKey::Config(quest_id));
+        config.map(|config| config.xp).unwrap_or_default()
+    }
+
+    /// Obtém a temporada atual de XP (a primeira é a 1)
+    pub fn get_current_season(env: &Env) -> u32 {
+        env.storage().instance()
+            .get(&DataKey::Season)
+            .unwrap_or(1)
+    }
+
+    /// Obtém o XP de um usuário em uma temporada
+    pub fn get_season_xp(env: &Env, season: u32, user: &Address) -> u128 {
+        env.storage().persistent()
+            .get(&DataKey::SeasonXp(season, user.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Obtém os usuários com mais XP em uma temporada
+    pub fn get_top_users(env: &Env, season: u32) -> Vec<LeaderboardEntry> {
+        env.storage().persistent()
+            .get(&DataKey::TopUsers(season))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Credita XP ao usuário no total e na temporada atual
+    pub fn add_xp(env: &Env, stats: &mut UserStats, user: &Address, xp: u128) {
+        if xp == 0 {
+            return;
+        }
+        stats.xp = stats.xp.checked_add(xp)
+            .unwrap_or_else(|| panic_with_error!(env, Error::ArithmeticOverflow));
+        let season = Self::get_current_season(env);
+        let season_xp = Self::get_season_xp(env, season, user).checked_add(xp)
+            .unwrap_or_else(|| panic_with_error!(env, Error::ArithmeticOverflow));
+        Self::set_season_xp(env, season, user, season_xp);
+    }
+
+    /// Grava o XP da temporada e reposiciona o usuário no ranking dela
+    /// O XP nunca diminui, então quem sai do ranking nunca tem mais XP que os que ficam
+    fn set_season_xp(env: &Env, season: u32, user: &Address, xp: u128) {
+        env.storage().persistent().set(&DataKey::SeasonXp(season, user.clone()), &xp);
+
+        let mut top_users = Self::get_top_users(env, season);
+        Self::rank_entry(&mut top_users, user, xp, TOP_USERS_SIZE);
+        env.storage().persistent().set(&DataKey::TopUsers(season), &top_users);
+    }
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct UserStats {
     pub total_participated: u32,
+    pub total_completed: u32,
     pub total_won: u32,
     pub total_rewards: u128,
     pub win_rate: u128, // Percentual * 100 (ex: 2500 = 25%)
+    pub xp: u128, // XP acumulado em todas as temporadas
 }
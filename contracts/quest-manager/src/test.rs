@@ -1,7 +1,111 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+extern crate std;
+
+use soroban_sdk::{
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Events, IssuerFlags, Ledger},
+    contract, contractimpl, symbol_short, token, xdr::{self, ToXdr}, Address, Bytes, BytesN, Env, IntoVal, String, Symbol, TryFromVal, Val
+};
+use std::rc::Rc;
+
+// Cria um token de recompensa (Stellar Asset Contract) e emite saldo para o admin
+fn create_reward_token(env: &Env, admin: &Address, amount: i128) -> Address {
+    let token_admin = Address::generate(env);
+    let reward_token = env.register_stellar_asset_contract_v2(token_admin).address();
+    token::StellarAssetClient::new(env, &reward_token).mint(admin, &amount);
+    reward_token
+}
+
+// Registra o contrato no endereço que o deployer obteria com um salt fixo
+fn deploy_quest_manager(env: &Env, deployer: &Address) -> Address {
+    let salt = BytesN::from_array(env, &[0; 32]);
+    let contract_id = env.deployer().with_address(deployer.clone(), salt).deployed_address();
+    env.register_contract(Some(&contract_id), QuestManager)
+}
+
+// Configuração avançada sem nenhuma restrição, para os testes alterarem só o necessário
+fn default_quest_config(env: &Env) -> QuestConfig {
+    QuestConfig {
+        allow_multiple_entries: false,
+        max_entries_per_user: 0,
+        max_participants: 0,
+        require_kyc: false,
+        min_account_age: 0,
+        geographic_restrictions: String::from_str(env, ""),
+        progress_adapter: None,
+        referrer_bonus: 0,
+        badges: Vec::new(env),
+        xp: XpRewards::default(),
+    }
+}
+
+// Endereço de uma conta Stellar (G...) derivado de uma semente fixa
+fn account_address(env: &Env, seed: u8) -> Address {
+    let account_id = xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256([seed; 32])));
+    Address::try_from_val(env, &xdr::ScAddress::Account(account_id)).unwrap()
+}
+
+// Cria a entrada de conta no ledger com saldo em XLM nativo (stroops)
+fn create_account(env: &Env, seed: u8, balance: i64) -> Address {
+    let account_id = xdr::AccountId(xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256([seed; 32])));
+    let key = Rc::new(xdr::LedgerKey::Account(xdr::LedgerKeyAccount {
+        account_id: account_id.clone(),
+    }));
+    let entry = Rc::new(xdr::LedgerEntry {
+        data: xdr::LedgerEntryData::Account(xdr::AccountEntry {
+            account_id,
+            balance,
+            flags: 0,
+            home_domain: Default::default(),
+            inflation_dest: None,
+            num_sub_entries: 0,
+            seq_num: xdr::SequenceNumber(0),
+            thresholds: xdr::Thresholds([1; 4]),
+            signers: xdr::VecM::default(),
+            ext: xdr::AccountEntryExt::V0,
+        }),
+        last_modified_ledger_seq: 0,
+        ext: xdr::LedgerEntryExt::V0,
+    });
+
+    let budget = env.host().budget_cloned();
+    env.host()
+        .with_mut_storage(|storage| storage.put(&key, &entry, None, &budget))
+        .unwrap();
+
+    account_address(env, seed)
+}
+
+// Token customizado mínimo que rejeita transferências para um endereço bloqueado
+#[contract]
+pub struct RejectingToken;
+
+#[contractimpl]
+impl RejectingToken {
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        let balance = Self::balance(env.clone(), to.clone());
+        env.storage().persistent().set(&to, &(balance + amount));
+    }
+
+    pub fn block(env: Env, user: Address) {
+        env.storage().instance().set(&symbol_short!("blocked"), &user);
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&id).unwrap_or(0)
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        let blocked: Option<Address> = env.storage().instance().get(&symbol_short!("blocked"));
+        if blocked == Some(to.clone()) {
+            panic!("recipient rejected");
+        }
+        Self::mint(env.clone(), from, -amount);
+        Self::mint(env, to, amount);
+    }
+}
 
 #[test]
 fn test_create_quest() {
@@ -9,7 +113,7 @@ fn test_create_quest() {
     env.mock_all_auths();
 
     let admin = Address::generate(&env);
-    let reward_token = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 5000);
     let contract_id = env.register_contract(None, QuestManager);
 
     let client = QuestManagerClient::new(&env, &contract_id);
@@ -34,7 +138,7 @@ fn test_create_quest() {
     assert_eq!(quest.admin, admin);
     assert_eq!(quest.reward_per_winner, 1000u128);
     assert_eq!(quest.max_winners, 5u32);
-    assert_eq!(quest.is_active, true);
+    assert!(quest.is_active);
 }
 
 #[test]
@@ -44,7 +148,7 @@ fn test_user_registration() {
 
     let admin = Address::generate(&env);
     let user = Address::generate(&env);
-    let reward_token = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 5000);
     let contract_id = env.register_contract(None, QuestManager);
 
     let client = QuestManagerClient::new(&env, &contract_id);
@@ -68,10 +172,2376 @@ fn test_user_registration() {
 
     // Verifica se o usuário está registrado
     let is_registered = client.is_user_registered(&quest_id, &user);
-    assert_eq!(is_registered, true);
+    assert!(is_registered);
 
     // Verifica se a quest aparece na lista do usuário
     let user_quests = client.get_user_quests(&user);
     assert_eq!(user_quests.len(), 1);
     assert_eq!(user_quests.get(0).unwrap(), quest_id);
 }
+
+#[test]
+fn test_update_quest_extends_and_funds() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 10000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &5u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &5000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.register(&quest_id, &user);
+
+    // Estende o prazo, edita o título e aumenta o número de ganhadores
+    let new_end = client.get_quest(&quest_id).end_timestamp + 3600;
+    client.update_quest(&quest_id, &QuestUpdate {
+        end_timestamp: Some(new_end),
+        title: Some(String::from_str(&env, "Extended Quest")),
+        description: None,
+        max_winners: Some(7u32),
+        reward_per_winner: None,
+    });
+
+    let quest = client.get_quest(&quest_id);
+    assert_eq!(quest.end_timestamp, new_end);
+    assert_eq!(quest.title, String::from_str(&env, "Extended Quest"));
+    assert_eq!(quest.max_winners, 7u32);
+    assert_eq!(quest.total_reward_pool, 7000u128);
+
+    // O financiamento adicional é retirado do saldo do admin
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&admin), 3000);
+    assert_eq!(token_client.balance(&contract_id), 7000);
+}
+
+#[test]
+fn test_update_quest_cannot_shorten_after_registration() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 5000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &5u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &5000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    let no_changes = QuestUpdate {
+        end_timestamp: None,
+        title: None,
+        description: None,
+        max_winners: None,
+        reward_per_winner: None,
+    };
+
+    // Antes de qualquer registro o prazo ainda pode ser reduzido
+    client.update_quest(&quest_id, &QuestUpdate { end_timestamp: Some(3000), ..no_changes.clone() });
+    assert_eq!(client.get_quest(&quest_id).end_timestamp, 3000);
+
+    client.register(&quest_id, &user);
+
+    let result = client.try_update_quest(&quest_id, &QuestUpdate { end_timestamp: Some(2000), ..no_changes.clone() });
+    assert_eq!(result, Err(Ok(Error::InvalidDuration.into())));
+
+    let result = client.try_update_quest(&quest_id, &QuestUpdate { reward_per_winner: Some(500), ..no_changes });
+    assert_eq!(result, Err(Ok(Error::InvalidRewardAmount.into())));
+}
+
+#[test]
+fn test_fund_quest_increases_reward() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 5000);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&sponsor, &5000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &5u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &5000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.fund_quest(&quest_id, &sponsor, &2500u128, &FundingMode::IncreaseReward);
+
+    let quest = client.get_quest(&quest_id);
+    assert_eq!(quest.total_reward_pool, 7500u128);
+    assert_eq!(quest.reward_per_winner, 1500u128);
+    assert_eq!(quest.max_winners, 5u32);
+
+    assert_eq!(client.get_contribution(&quest_id, &admin), 5000u128);
+    assert_eq!(client.get_contribution(&quest_id, &sponsor), 2500u128);
+    assert_eq!(client.get_sponsors(&quest_id).len(), 2);
+}
+
+#[test]
+fn test_unused_pool_refunded_to_sponsors_at_resolution() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&sponsor, &3000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+    client.fund_quest(&quest_id, &sponsor, &3000u128, &FundingMode::PoolOnly);
+
+    client.register(&quest_id, &user);
+    client.mark_user_eligible(&quest_id, &user);
+
+    // Dos 3000 que sobram além do prêmio, cada um recebe na proporção do que aportou (1000:3000)
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&user), 1000);
+    assert_eq!(token_client.balance(&admin), 750);
+    assert_eq!(token_client.balance(&sponsor), 2250);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_cancel_quest_refunds_sponsors() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let sponsor = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 5000);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&sponsor, &3000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &5u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &5000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.fund_quest(&quest_id, &sponsor, &3000u128, &FundingMode::IncreaseWinners);
+    assert_eq!(client.get_quest(&quest_id).max_winners, 8u32);
+
+    client.cancel_quest(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&admin), 5000);
+    assert_eq!(token_client.balance(&sponsor), 3000);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_reward_bundle_paid_to_each_winner() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let partner_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.add_bonus_reward(&quest_id, &partner_token, &500u128);
+
+    let result = client.try_add_bonus_reward(&quest_id, &reward_token, &500u128);
+    assert_eq!(result, Err(Ok(Error::InvalidRewardToken.into())));
+
+    let bundle = client.get_reward_bundle(&quest_id);
+    assert_eq!(bundle.len(), 2);
+    assert_eq!(bundle.get(1).unwrap().total_pool, 1000u128);
+
+    client.register(&quest_id, &user);
+    client.mark_user_eligible(&quest_id, &user);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    // A vaga sem ganhador devolve sua parte do token adicional ao admin
+    assert_eq!(token::Client::new(&env, &partner_token).balance(&admin), 500);
+
+    client.distribute_rewards(&quest_id);
+
+    assert_eq!(token::Client::new(&env, &reward_token).balance(&user), 1000);
+    assert_eq!(token::Client::new(&env, &partner_token).balance(&user), 500);
+
+    let result = client.try_distribute_rewards(&quest_id);
+    assert_eq!(result, Err(Ok(Error::RewardsAlreadyDistributed.into())));
+}
+
+#[test]
+fn test_cancel_quest_refunds_each_bundle_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let partner_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.add_bonus_reward(&quest_id, &partner_token, &500u128);
+    assert_eq!(token::Client::new(&env, &partner_token).balance(&admin), 0);
+
+    client.cancel_quest(&quest_id);
+
+    assert_eq!(token::Client::new(&env, &reward_token).balance(&admin), 2000);
+    assert_eq!(token::Client::new(&env, &partner_token).balance(&admin), 1000);
+}
+
+#[test]
+fn test_prize_table_pays_by_rank() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    let third = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 900);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &300u128,
+        &3u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &900u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    let mut tiers = Vec::new(&env);
+    tiers.push_back(PrizeTier { last_rank: 1, amount: 500 });
+    tiers.push_back(PrizeTier { last_rank: 3, amount: 200 });
+    client.set_prize_table(&quest_id, &tiers);
+
+    for user in [&first, &second, &third] {
+        client.register(&quest_id, user);
+        client.mark_user_eligible(&quest_id, user);
+    }
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    let prizes = client.get_winner_prizes(&quest_id);
+    assert_eq!(prizes.get(0).unwrap(), WinnerPrize { winner: first.clone(), rank: 1, amount: 500 });
+    assert_eq!(prizes.get(2).unwrap(), WinnerPrize { winner: third.clone(), rank: 3, amount: 200 });
+
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&first), 500);
+    assert_eq!(token_client.balance(&second), 200);
+    assert_eq!(token_client.balance(&third), 200);
+}
+
+#[test]
+fn test_prize_table_must_be_covered_by_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 900);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &300u128,
+        &3u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &900u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    let mut tiers = Vec::new(&env);
+    tiers.push_back(PrizeTier { last_rank: 1, amount: 500 });
+    tiers.push_back(PrizeTier { last_rank: 5, amount: 200 });
+    let result = client.try_set_prize_table(&quest_id, &tiers);
+    assert_eq!(result, Err(Ok(Error::InsufficientRewardPool.into())));
+
+    let mut unordered = Vec::new(&env);
+    unordered.push_back(PrizeTier { last_rank: 2, amount: 200 });
+    unordered.push_back(PrizeTier { last_rank: 2, amount: 100 });
+    let result = client.try_set_prize_table(&quest_id, &unordered);
+    assert_eq!(result, Err(Ok(Error::InvalidPrizeTable.into())));
+}
+
+#[test]
+fn test_pro_rata_splits_pool_by_score() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let whale = Address::generate(&env);
+    let trader = Address::generate(&env);
+    let minnow = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    // Pool de 1000 com limite de 500 por usuário
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &500u128,
+        &10u32,
+        &DistributionType::ProRata,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Volume Quest"),
+        &String::from_str(&env, "Split by traded volume"),
+    );
+
+    for user in [&whale, &trader, &minnow] {
+        client.register(&quest_id, user);
+    }
+    client.submit_score(&quest_id, &whale, &8000u128);
+    client.submit_score(&quest_id, &trader, &1000u128);
+    client.submit_score(&quest_id, &minnow, &1000u128);
+    assert_eq!(client.get_score(&quest_id, &whale), 8000u128);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    // O whale é limitado a 500 e o restante é dividido entre os demais
+    let prizes = client.get_winner_prizes(&quest_id);
+    assert_eq!(prizes.len(), 3);
+    assert_eq!(prizes.get(0).unwrap(), WinnerPrize { winner: whale.clone(), rank: 1, amount: 500 });
+    assert_eq!(prizes.get(1).unwrap().amount, 250);
+    assert_eq!(prizes.get(2).unwrap().amount, 250);
+
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&whale), 500);
+    assert_eq!(token_client.balance(&trader), 250);
+    assert_eq!(token_client.balance(&minnow), 250);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_pro_rata_dust_goes_to_top_scorer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    let third = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &10u32,
+        &DistributionType::ProRata,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &100u128,
+        &String::from_str(&env, "Volume Quest"),
+        &String::from_str(&env, "Split by traded volume"),
+    );
+
+    for user in [&first, &second, &third] {
+        client.register(&quest_id, user);
+    }
+    client.submit_score(&quest_id, &second, &1u128);
+    client.submit_score(&quest_id, &first, &1u128);
+    client.submit_score(&quest_id, &third, &1u128);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    // 100 / 3 = 33 para cada um; a poeira de 1 vai para o primeiro a pontuar no empate
+    let prizes = client.get_winner_prizes(&quest_id);
+    assert_eq!(prizes.get(0).unwrap(), WinnerPrize { winner: second.clone(), rank: 1, amount: 34 });
+    assert_eq!(prizes.get(1).unwrap().amount, 33);
+    assert_eq!(prizes.get(2).unwrap().amount, 33);
+}
+
+#[test]
+fn test_pro_rata_rejects_overflowing_scores() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 101);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &10u32,
+        &DistributionType::ProRata,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &101u128,
+        &String::from_str(&env, "Volume Quest"),
+        &String::from_str(&env, "Split by traded volume"),
+    );
+
+    // Pontuações cuja soma estoura u128 não podem gerar alocações além do pool
+    for user in [&alice, &bob] {
+        client.register(&quest_id, user);
+        client.submit_score(&quest_id, user, &(u128::MAX / 2 + 1));
+    }
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    let result = client.try_resolve_quest(&quest_id);
+    assert_eq!(result, Err(Ok(Error::ArithmeticOverflow.into())));
+}
+
+#[test]
+fn test_weighted_raffle_draws_by_tickets() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let casual = Address::generate(&env);
+    let inactive = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &500u128,
+        &2u32,
+        &DistributionType::WeightedRaffle,
+        &QuestType::TokenHold(reward_token.clone(), 100u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Holding Raffle"),
+        &String::from_str(&env, "More tickets for longer holding"),
+    );
+
+    for user in [&holder, &casual, &inactive] {
+        client.register(&quest_id, user);
+    }
+    client.set_tickets(&quest_id, &holder, &10u32);
+    client.mark_user_eligible(&quest_id, &casual);
+    client.set_tickets(&quest_id, &inactive, &0u32);
+
+    // A tabela de bilhetes pode ser consultada antes do sorteio
+    let tickets = client.get_tickets(&quest_id);
+    assert_eq!(tickets.len(), 3);
+    assert_eq!(tickets.get(0).unwrap(), TicketEntry { user: holder.clone(), tickets: 10 });
+    assert_eq!(tickets.get(1).unwrap(), TicketEntry { user: casual.clone(), tickets: 1 });
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    // Sem reposição, os dois que têm bilhetes ganham e quem tem zero nunca é sorteado
+    let winners = client.get_winners(&quest_id);
+    assert_eq!(winners.len(), 2);
+    assert!(winners.contains(&holder));
+    assert!(winners.contains(&casual));
+    assert!(!winners.contains(&inactive));
+}
+
+#[test]
+fn test_set_tickets_requires_weighted_raffle() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &500u128,
+        &2u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.register(&quest_id, &user);
+
+    let result = client.try_set_tickets(&quest_id, &user, &5u32);
+    assert_eq!(result, Err(Ok(Error::InvalidDistribution.into())));
+}
+
+#[test]
+fn test_leaderboard_pays_top_scores() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Leaderboard,
+        &QuestType::TradeVolume(100u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Top Traders"),
+        &String::from_str(&env, "Top 2 traders this week"),
+    );
+
+    for user in [&alice, &bob, &carol] {
+        client.register(&quest_id, user);
+    }
+
+    // Progresso na tarefa não classifica ninguém: o ranking vem só das pontuações
+    client.report_progress(&quest_id, &alice, &admin, &0u32, &150u128);
+    assert!(client.get_leaderboard(&quest_id).is_empty());
+
+    client.submit_score(&quest_id, &alice, &500u128);
+    client.submit_score(&quest_id, &bob, &700u128);
+    client.submit_score(&quest_id, &carol, &300u128);
+
+    // O ranking guarda apenas os 2 melhores; carol fica de fora
+    let leaderboard = client.get_leaderboard(&quest_id);
+    assert_eq!(leaderboard.len(), 2);
+    assert_eq!(leaderboard.get(0).unwrap(), LeaderboardEntry { user: bob.clone(), score: 700 });
+
+    // Carol empata com bob depois dele, então fica atrás no desempate
+    client.submit_score(&quest_id, &carol, &700u128);
+    let leaderboard = client.get_leaderboard(&quest_id);
+    assert_eq!(leaderboard.get(0).unwrap().user, bob);
+    assert_eq!(leaderboard.get(1).unwrap().user, carol);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+
+    let result = client.try_submit_score(&quest_id, &alice, &900u128);
+    assert_eq!(result, Err(Ok(Error::QuestExpired.into())));
+
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&bob), 1000);
+    assert_eq!(token_client.balance(&carol), 1000);
+    assert_eq!(token_client.balance(&alice), 0);
+}
+
+#[test]
+fn test_leaderboard_recovers_users_after_score_drop() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Leaderboard,
+        &QuestType::TradeVolume(0u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Top Traders"),
+        &String::from_str(&env, "Top 2 traders this week"),
+    );
+
+    for user in [&alice, &bob, &carol] {
+        client.register(&quest_id, user);
+    }
+    client.submit_score(&quest_id, &alice, &100u128);
+    client.submit_score(&quest_id, &bob, &90u128);
+    client.submit_score(&quest_id, &carol, &80u128);
+
+    // Quem estava fora do ranking volta quando uma pontuação acima dele cai
+    client.submit_score(&quest_id, &alice, &10u128);
+    assert_eq!(client.get_leaderboard(&quest_id), soroban_sdk::vec![&env,
+        LeaderboardEntry { user: bob.clone(), score: 90 },
+        LeaderboardEntry { user: carol.clone(), score: 80 },
+    ]);
+
+    // Pontuação zero não entra no ranking nem ganha
+    client.submit_score(&quest_id, &carol, &0u128);
+    client.submit_score(&quest_id, &alice, &0u128);
+    assert_eq!(client.get_leaderboard(&quest_id), soroban_sdk::vec![&env,
+        LeaderboardEntry { user: bob.clone(), score: 90 },
+    ]);
+}
+
+#[test]
+fn test_vesting_releases_rewards_linearly() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Vesting Quest"),
+        &String::from_str(&env, "Rewards vest over 1000 seconds"),
+    );
+
+    // Cliff de 100s e liberação total em 1000s
+    client.set_vesting(&quest_id, &100u64, &1000u64);
+
+    client.register(&quest_id, &user);
+    client.mark_user_eligible(&quest_id, &user);
+
+    env.ledger().with_mut(|li| li.timestamp = 5000);
+    client.resolve_quest(&quest_id);
+
+    let result = client.try_distribute_rewards(&quest_id);
+    assert_eq!(result, Err(Ok(Error::RewardsVesting.into())));
+
+    // Antes do cliff nada é liberado
+    env.ledger().with_mut(|li| li.timestamp = 5050);
+    let result = client.try_claim_vested(&quest_id, &user);
+    assert_eq!(result, Err(Ok(Error::NothingToClaim.into())));
+
+    env.ledger().with_mut(|li| li.timestamp = 5250);
+    assert_eq!(client.claim_vested(&quest_id, &user), 250u128);
+
+    let status = client.get_vesting_status(&quest_id, &user);
+    assert_eq!(status, VestingStatus { total: 1000, vested: 250, claimed: 250, remaining: 750 });
+
+    env.ledger().with_mut(|li| li.timestamp = 7000);
+    assert_eq!(client.claim_vested(&quest_id, &user), 750u128);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&user), 1000);
+}
+
+#[test]
+fn test_pro_rata_vesting_refunds_unallocated_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    // Um único participante só pode receber 100 dos 1000 do pool
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &10u32,
+        &DistributionType::ProRata,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Volume Quest"),
+        &String::from_str(&env, "Split by traded volume"),
+    );
+    client.set_vesting(&quest_id, &0u64, &1000u64);
+
+    client.register(&quest_id, &user);
+    client.submit_score(&quest_id, &user, &5000u128);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&admin), 900);
+
+    env.ledger().with_mut(|li| li.timestamp += 1000);
+    assert_eq!(client.claim_vested(&quest_id, &user), 100u128);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_streaming_accrues_per_second() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 4600);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    // Emite 1 token por segundo durante 1 hora, dividido entre os elegíveis
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1u128,
+        &10u32,
+        &DistributionType::Streaming,
+        &QuestType::PoolPosition(1000u128),
+        &3600u64,
+        &3600u128,
+        &String::from_str(&env, "Liquidity Mining"),
+        &String::from_str(&env, "Stay in the pool to earn"),
+    );
+
+    // Streaming não paga tokens adicionais
+    let result = client.try_add_bonus_reward(&quest_id, &reward_token, &1u128);
+    assert_eq!(result, Err(Ok(Error::InvalidDistribution.into())));
+
+    // Nem usa tabela de prêmios, que mudaria o valor exigido do pool
+    let result = client.try_set_prize_table(&quest_id, &soroban_sdk::vec![&env, PrizeTier { last_rank: 1, amount: 1 }]);
+    assert_eq!(result, Err(Ok(Error::InvalidDistribution.into())));
+
+    // Uma emissão que estoura o pool exigido é rejeitada
+    let result = client.try_create_quest(
+        &admin,
+        &reward_token,
+        &u128::MAX,
+        &10u32,
+        &DistributionType::Streaming,
+        &QuestType::PoolPosition(1000u128),
+        &3600u64,
+        &3600u128,
+        &String::from_str(&env, "Liquidity Mining"),
+        &String::from_str(&env, "Stay in the pool to earn"),
+    );
+    assert_eq!(result, Err(Ok(Error::InvalidRewardAmount.into())));
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+
+    // Ninguém elegível nos primeiros 100s
+    env.ledger().with_mut(|li| li.timestamp = 1100);
+    client.mark_user_eligible(&quest_id, &alice);
+
+    env.ledger().with_mut(|li| li.timestamp = 1300);
+    client.mark_user_eligible(&quest_id, &bob);
+
+    env.ledger().with_mut(|li| li.timestamp = 1500);
+    assert_eq!(client.get_stream_claimable(&quest_id, &alice), 300u128);
+    assert_eq!(client.get_stream_claimable(&quest_id, &bob), 100u128);
+
+    client.mark_user_ineligible(&quest_id, &bob);
+    assert_eq!(client.claim_stream(&quest_id, &alice), 300u128);
+
+    // Depois que bob sai, alice volta a receber tudo
+    env.ledger().with_mut(|li| li.timestamp = 1600);
+    assert_eq!(client.get_stream_claimable(&quest_id, &alice), 100u128);
+    assert_eq!(client.get_stream_claimable(&quest_id, &bob), 100u128);
+
+    // Um aporte extra não altera a emissão e sobra no pool
+    client.fund_quest(&quest_id, &admin, &1000u128, &FundingMode::PoolOnly);
+
+    // Ao resolver, o que foi emitido sem elegíveis e a sobra do pool voltam a quem financiou
+    env.ledger().with_mut(|li| li.timestamp = 5000);
+    client.resolve_quest(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&admin), 1100);
+
+    assert_eq!(client.claim_stream(&quest_id, &alice), 3100u128);
+    assert_eq!(client.claim_stream(&quest_id, &bob), 100u128);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_entry_fees_fund_prize_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let token_admin = token::StellarAssetClient::new(&env, &reward_token);
+    token_admin.mint(&alice, &100);
+    token_admin.mint(&bob, &100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Community Quest"),
+        &String::from_str(&env, "Funded by entry fees"),
+    );
+
+    // Taxa de 100 com 10% para o admin; o resto aumenta o prêmio por ganhador
+    client.set_entry_fee(&quest_id, &100u128, &1000u32, &FundingMode::IncreaseReward);
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+
+    let quest = client.get_quest(&quest_id);
+    assert_eq!(quest.total_reward_pool, 1180u128);
+    assert_eq!(quest.reward_per_winner, 1180u128);
+    assert_eq!(client.get_entry_fee(&quest_id).unwrap().collected_cut, 20u128);
+
+    client.mark_user_eligible(&quest_id, &alice);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&alice), 1180);
+    assert_eq!(token_client.balance(&admin), 20);
+}
+
+#[test]
+fn test_unregister_refunds_entry_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let token_admin = token::StellarAssetClient::new(&env, &reward_token);
+    token_admin.mint(&alice, &100);
+    token_admin.mint(&bob, &100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Community Quest"),
+        &String::from_str(&env, "Funded by entry fees"),
+    );
+    client.set_entry_fee(&quest_id, &100u128, &1000u32, &FundingMode::IncreaseReward);
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+    client.mark_user_eligible(&quest_id, &alice);
+
+    // Quem já é elegível não pode sair
+    let result = client.try_unregister(&quest_id, &alice);
+    assert_eq!(result, Err(Ok(Error::UserAlreadyEligible.into())));
+
+    client.unregister(&quest_id, &bob);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&bob), 100);
+    assert!(!client.is_user_registered(&quest_id, &bob));
+    assert!(client.get_user_quests(&bob).is_empty());
+    assert_eq!(client.get_contribution(&quest_id, &bob), 0u128);
+    assert_eq!(client.get_sponsors(&quest_id).len(), 2);
+
+    let quest = client.get_quest(&quest_id);
+    assert_eq!(quest.total_reward_pool, 1090u128);
+    assert_eq!(quest.reward_per_winner, 1090u128);
+    assert_eq!(client.get_entry_fee(&quest_id).unwrap().collected_cut, 10u128);
+    assert_eq!(client.get_quest_stats(&quest_id).total_registered, 1);
+
+    let result = client.try_unregister(&quest_id, &bob);
+    assert_eq!(result, Err(Ok(Error::UserNotRegistered.into())));
+}
+
+#[test]
+fn test_multiple_entries_per_user() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 3000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &3u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &3000u128,
+        &String::from_str(&env, "Repeatable Quest"),
+        &String::from_str(&env, "Each completion takes a slot"),
+    );
+
+    let mut config = QuestConfig {
+        allow_multiple_entries: true,
+        ..default_quest_config(&env)
+    };
+    let result = client.try_set_quest_config(&quest_id, &config);
+    assert_eq!(result, Err(Ok(Error::InvalidQuestConfig.into())));
+
+    config.max_entries_per_user = 2;
+    client.set_quest_config(&quest_id, &config);
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+
+    // A terceira conclusão de alice passa do limite e é ignorada
+    for _ in 0..3 {
+        client.mark_user_eligible(&quest_id, &alice);
+    }
+    client.mark_user_eligible(&quest_id, &bob);
+
+    assert_eq!(client.get_entries(&quest_id, &alice), 2);
+    assert_eq!(client.get_entries(&quest_id, &bob), 1);
+    assert_eq!(client.get_winners(&quest_id).len(), 3);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&alice), 2000);
+    assert_eq!(token_client.balance(&bob), 1000);
+
+    // Duas entradas premiadas contam como uma única vitória
+    let stats = client.get_user_stats(&alice);
+    assert_eq!(stats.total_won, 1);
+    assert_eq!(stats.total_rewards, 2000);
+    assert_eq!(stats.win_rate, 10000);
+}
+
+#[test]
+fn test_vested_bonus_scales_with_entries() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let partner_token = create_reward_token(&env, &admin, 200);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Repeatable Quest"),
+        &String::from_str(&env, "Each completion takes a slot"),
+    );
+    client.add_bonus_reward(&quest_id, &partner_token, &100u128);
+    client.set_vesting(&quest_id, &0u64, &1000u64);
+    client.set_quest_config(&quest_id, &QuestConfig {
+        allow_multiple_entries: true,
+        max_entries_per_user: 2,
+        ..default_quest_config(&env)
+    });
+
+    client.register(&quest_id, &alice);
+    client.mark_user_eligible(&quest_id, &alice);
+    client.mark_user_eligible(&quest_id, &alice);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+
+    // Dois prêmios dão direito a duas vezes o token adicional, como na distribuição direta
+    env.ledger().with_mut(|li| li.timestamp += 1000);
+    assert_eq!(client.claim_vested(&quest_id, &alice), 2000u128);
+    assert_eq!(token::Client::new(&env, &partner_token).balance(&alice), 200);
+}
+
+#[test]
+fn test_register_for_with_relayer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let relayer = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&relayer, &100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Gasless Quest"),
+        &String::from_str(&env, "Onboarding through a relayer"),
+    );
+    client.set_entry_fee(&quest_id, &100u128, &0u32, &FundingMode::PoolOnly);
+
+    client.register_for(&quest_id, &user, &relayer, &0u64);
+
+    // O usuário assina apenas (quest_id, nonce); o relayer paga a taxa de inscrição
+    assert!(env.auths().contains(&(
+        user.clone(),
+        AuthorizedInvocation {
+            function: AuthorizedFunction::Contract((
+                contract_id.clone(),
+                Symbol::new(&env, "register_for"),
+                (quest_id, 0u64).into_val(&env),
+            )),
+            sub_invocations: std::vec![],
+        }
+    )));
+    assert!(client.is_user_registered(&quest_id, &user));
+    assert_eq!(client.get_nonce(&user), 1);
+    assert_eq!(client.get_contribution(&quest_id, &relayer), 100u128);
+
+    // A mesma assinatura não pode ser reaproveitada
+    client.unregister(&quest_id, &user);
+    let result = client.try_register_for(&quest_id, &user, &relayer, &0u64);
+    assert_eq!(result, Err(Ok(Error::InvalidNonce.into())));
+
+    // Ao sair, a taxa volta para o relayer que pagou
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&relayer), 100);
+    assert_eq!(token_client.balance(&user), 0);
+}
+
+#[test]
+fn test_waitlist_promotes_on_unregister() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let dave = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let token_admin = token::StellarAssetClient::new(&env, &reward_token);
+    for user in [&alice, &bob, &carol, &dave] {
+        token_admin.mint(user, &100);
+    }
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Beta Program"),
+        &String::from_str(&env, "Limited slots"),
+    );
+    client.set_quest_config(&quest_id, &QuestConfig {
+        max_participants: 1,
+        ..default_quest_config(&env)
+    });
+    client.set_entry_fee(&quest_id, &100u128, &0u32, &FundingMode::PoolOnly);
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+    client.register(&quest_id, &carol);
+
+    // Quem está na fila já pagou, mas a taxa só entra no pool com a promoção
+    assert!(!client.is_user_registered(&quest_id, &bob));
+    assert_eq!(client.get_waitlist(&quest_id).len(), 2);
+    assert_eq!(client.get_quest(&quest_id).total_reward_pool, 1100u128);
+
+    client.unregister(&quest_id, &alice);
+
+    assert!(client.is_user_registered(&quest_id, &bob));
+    assert_eq!(client.get_waitlist(&quest_id), soroban_sdk::vec![&env, carol.clone()]);
+    assert_eq!(client.get_quest(&quest_id).total_reward_pool, 1100u128);
+    assert_eq!(client.get_contribution(&quest_id, &bob), 100u128);
+
+    client.set_registration_open(&quest_id, &false);
+    assert!(!client.is_registration_open(&quest_id));
+    let result = client.try_register(&quest_id, &dave);
+    assert_eq!(result, Err(Ok(Error::RegistrationClosed.into())));
+
+    // No cancelamento, quem ficou na fila recebe a taxa de volta
+    client.cancel_quest(&quest_id);
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&alice), 100);
+    assert_eq!(token_client.balance(&bob), 100);
+    assert_eq!(token_client.balance(&carol), 100);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_waitlist_skips_users_no_longer_allowed() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let token_admin = token::StellarAssetClient::new(&env, &reward_token);
+    for user in [&alice, &bob, &carol] {
+        token_admin.mint(user, &100);
+    }
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Beta Program"),
+        &String::from_str(&env, "Limited slots"),
+    );
+    client.set_quest_config(&quest_id, &QuestConfig {
+        max_participants: 1,
+        ..default_quest_config(&env)
+    });
+    client.set_entry_fee(&quest_id, &100u128, &0u32, &FundingMode::PoolOnly);
+    client.set_allowlist(&quest_id, &soroban_sdk::vec![&env, alice.clone(), bob.clone(), carol.clone()], &true);
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+    client.register(&quest_id, &carol);
+
+    // bob sai da allowlist enquanto espera: é pulado na promoção e recebe a taxa de volta
+    client.set_allowlist(&quest_id, &soroban_sdk::vec![&env, bob.clone()], &false);
+    client.unregister(&quest_id, &alice);
+
+    assert!(!client.is_user_registered(&quest_id, &bob));
+    assert!(client.is_user_registered(&quest_id, &carol));
+    assert!(client.get_waitlist(&quest_id).is_empty());
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&bob), 100);
+    assert_eq!(token_client.balance(&carol), 0);
+}
+
+#[test]
+fn test_unreachable_waitlisted_payer_does_not_block_resolution() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let issuer_admin = Address::generate(&env);
+    let classic = env.register_stellar_asset_contract_v2(issuer_admin);
+    classic.issuer().set_flag(IssuerFlags::RevocableFlag);
+    let reward_token = classic.address();
+    let sac_client = token::StellarAssetClient::new(&env, &reward_token);
+    sac_client.mint(&admin, &1000);
+    sac_client.mint(&alice, &100);
+    sac_client.mint(&bob, &100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Beta Program"),
+        &String::from_str(&env, "Limited slots"),
+    );
+    client.set_quest_config(&quest_id, &QuestConfig {
+        max_participants: 1,
+        ..default_quest_config(&env)
+    });
+    client.set_entry_fee(&quest_id, &100u128, &0u32, &FundingMode::PoolOnly);
+
+    client.register(&quest_id, &alice);
+    client.register(&quest_id, &bob);
+    sac_client.set_authorized(&bob, &false);
+
+    // A taxa de bob fica devida e a quest é resolvida normalmente
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    assert_eq!(client.get_owed_rewards(&quest_id, &bob), soroban_sdk::vec![&env,
+        OwedReward { token: reward_token.clone(), amount: 100 },
+    ]);
+
+    sac_client.set_authorized(&bob, &true);
+    client.claim_owed(&quest_id, &bob);
+    assert_eq!(token::Client::new(&env, &reward_token).balance(&bob), 100);
+}
+
+#[test]
+fn test_unregister_refund_owed_to_unreachable_payer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let issuer_admin = Address::generate(&env);
+    let classic = env.register_stellar_asset_contract_v2(issuer_admin);
+    classic.issuer().set_flag(IssuerFlags::RevocableFlag);
+    let reward_token = classic.address();
+    let sac_client = token::StellarAssetClient::new(&env, &reward_token);
+    sac_client.mint(&admin, &1000);
+    sac_client.mint(&alice, &100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Community Quest"),
+        &String::from_str(&env, "Funded by entry fees"),
+    );
+    client.set_entry_fee(&quest_id, &100u128, &1000u32, &FundingMode::PoolOnly);
+
+    client.register(&quest_id, &alice);
+    sac_client.set_authorized(&alice, &false);
+
+    // A saída não falha: a devolução fica devida até alice poder receber
+    client.unregister(&quest_id, &alice);
+    assert!(!client.is_user_registered(&quest_id, &alice));
+    assert_eq!(client.get_owed_rewards(&quest_id, &alice), soroban_sdk::vec![&env,
+        OwedReward { token: reward_token.clone(), amount: 100 },
+    ]);
+
+    sac_client.set_authorized(&alice, &true);
+    client.claim_owed(&quest_id, &alice);
+    assert_eq!(token::Client::new(&env, &reward_token).balance(&alice), 100);
+}
+
+#[test]
+fn test_entry_fees_refunded_on_cancel() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&user, &100);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Community Quest"),
+        &String::from_str(&env, "Funded by entry fees"),
+    );
+
+    client.set_entry_fee(&quest_id, &100u128, &2500u32, &FundingMode::PoolOnly);
+    client.register(&quest_id, &user);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&user), 0);
+
+    client.cancel_quest(&quest_id);
+
+    // A parte do pool volta na hora e a parte do admin fica devida até o resgate
+    assert_eq!(token_client.balance(&user), 75);
+    assert_eq!(client.get_owed_rewards(&quest_id, &user), soroban_sdk::vec![&env,
+        OwedReward { token: reward_token.clone(), amount: 25 },
+    ]);
+    client.claim_owed(&quest_id, &user);
+
+    assert_eq!(token_client.balance(&user), 100);
+    assert_eq!(token_client.balance(&admin), 1000);
+    assert_eq!(token_client.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_protocol_fee_on_reward_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let owner = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let partner = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1100);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&partner, &1000);
+    let contract_id = deploy_quest_manager(&env, &owner);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    // Taxa de 10% sobre o pool; o parceiro é isento
+    client.initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &1000u32, &FeeBase::RewardPool);
+    client.set_fee_exemption(&partner, &true);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    let exempt_quest_id = client.create_quest(
+        &partner,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Partner Quest"),
+        &String::from_str(&env, "Fee exempt"),
+    );
+
+    // A taxa é cobrada além do pool e fica separada dele
+    assert_eq!(client.get_quest(&quest_id).total_reward_pool, 1000u128);
+    assert_eq!(client.get_protocol_fees(&reward_token), 100u128);
+    assert_eq!(client.get_quest_fee(&exempt_quest_id), None);
+
+    assert_eq!(client.withdraw_protocol_fees(&reward_token), 100u128);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&treasury), 100);
+    assert_eq!(token_client.balance(&admin), 0);
+    assert_eq!(token_client.balance(&contract_id), 2000);
+}
+
+#[test]
+fn test_protocol_fee_on_payouts() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let owner = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = deploy_quest_manager(&env, &owner);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    // Só quem implantou o contrato pode se tornar owner, e a taxa tem teto
+    let stranger = Address::generate(&env);
+    let result = client.try_initialize_protocol(&stranger, &BytesN::from_array(&env, &[0; 32]), &stranger, &0u32, &FeeBase::RewardPool);
+    assert_eq!(result, Err(Ok(Error::Unauthorized.into())));
+    let result = client.try_initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &10_000u32, &FeeBase::Payout);
+    assert_eq!(result, Err(Ok(Error::InvalidFee.into())));
+
+    client.initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &0u32, &FeeBase::RewardPool);
+    client.set_protocol_fee(&250u32, &FeeBase::Payout);
+
+    let result = client.try_initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &0u32, &FeeBase::RewardPool);
+    assert_eq!(result, Err(Ok(Error::AlreadyInitialized.into())));
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.register(&quest_id, &user);
+    client.mark_user_eligible(&quest_id, &user);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&user), 975);
+    assert_eq!(client.get_protocol_fees(&reward_token), 25u128);
+}
+
+#[test]
+fn test_native_xlm_rewards_owed_until_account_exists() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let native_token = env.deployer()
+        .with_stellar_asset(Bytes::from_array(&env, &[0, 0, 0, 0]))
+        .deploy();
+    let admin = create_account(&env, 1, 1_000_000_000);
+    let user1 = Address::generate(&env);
+    let user2 = account_address(&env, 2); // Conta ainda não existe no ledger
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+    assert_eq!(client.get_native_token(), native_token);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &client.get_native_token(),
+        &1000u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "XLM Quest"),
+        &String::from_str(&env, "Rewards paid in native XLM"),
+    );
+
+    for user in [&user1, &user2] {
+        client.register(&quest_id, user);
+        client.mark_user_eligible(&quest_id, user);
+    }
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    // A conta inexistente não trava a distribuição: o valor fica devido
+    let xlm = token::Client::new(&env, &native_token);
+    assert_eq!(xlm.balance(&user1), 1000);
+    assert_eq!(xlm.balance(&contract_id), 1000);
+    let owed = client.get_owed_rewards(&quest_id, &user2);
+    assert_eq!(owed.len(), 1);
+    assert_eq!(owed.get(0).unwrap(), OwedReward { token: native_token.clone(), amount: 1000 });
+
+    let result = client.try_claim_owed(&quest_id, &user2);
+    assert_eq!(result, Err(Ok(Error::NothingToClaim.into())));
+
+    // Depois que a conta é criada, o ganhador resgata o que era devido
+    create_account(&env, 2, 0);
+    client.claim_owed(&quest_id, &user2);
+    assert_eq!(xlm.balance(&user2), 1000);
+    assert!(client.get_owed_rewards(&quest_id, &user2).is_empty());
+}
+
+#[test]
+fn test_classic_asset_skips_deauthorized_winner() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let issuer_admin = Address::generate(&env);
+    let classic = env.register_stellar_asset_contract_v2(issuer_admin);
+    classic.issuer().set_flag(IssuerFlags::RevocableFlag);
+    let reward_token = classic.address();
+    let sac_client = token::StellarAssetClient::new(&env, &reward_token);
+    sac_client.mint(&admin, &2000);
+    let bonus_token = create_reward_token(&env, &admin, 200);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Classic Quest"),
+        &String::from_str(&env, "Rewards paid in a classic asset"),
+    );
+    client.add_bonus_reward(&quest_id, &bonus_token, &100u128);
+
+    for user in [&user1, &user2] {
+        client.register(&quest_id, user);
+        client.mark_user_eligible(&quest_id, user);
+    }
+
+    // O emissor revoga a autorização do segundo ganhador
+    sac_client.set_authorized(&user2, &false);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let reward_client = token::Client::new(&env, &reward_token);
+    let bonus_client = token::Client::new(&env, &bonus_token);
+    assert_eq!(reward_client.balance(&user1), 1000);
+    assert_eq!(bonus_client.balance(&user1), 100);
+    assert_eq!(reward_client.balance(&user2), 0);
+    assert_eq!(bonus_client.balance(&user2), 100); // Tokens sem restrição são pagos normalmente
+    assert_eq!(client.get_owed_rewards(&quest_id, &user2).get(0).unwrap().amount, 1000);
+
+    sac_client.set_authorized(&user2, &true);
+    let paid = client.claim_owed(&quest_id, &user2);
+    assert_eq!(paid.len(), 1);
+    assert_eq!(reward_client.balance(&user2), 1000);
+}
+
+#[test]
+fn test_failed_payout_recorded_as_owed() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let reward_token = env.register_contract(None, RejectingToken);
+    let token_client = RejectingTokenClient::new(&env, &reward_token);
+    token_client.mint(&admin, &2000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    for user in [&user1, &user2] {
+        client.register(&quest_id, user);
+        client.mark_user_eligible(&quest_id, user);
+    }
+
+    // O token rejeita o primeiro ganhador, mas o segundo ainda recebe
+    token_client.block(&user1);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    assert_eq!(token_client.balance(&user1), 0);
+    assert_eq!(token_client.balance(&user2), 1000);
+    assert_eq!(token_client.balance(&contract_id), 1000);
+    assert_eq!(client.get_owed_rewards(&quest_id, &user1).get(0).unwrap().amount, 1000);
+
+    let topics: Vec<Val> = (Symbol::new(&env, "payout_failed"),).into_val(&env);
+    let (_, _, data) = env.events().all().iter()
+        .find(|(_, event_topics, _)| *event_topics == topics)
+        .unwrap();
+    let failed = PayoutFailedEvent::try_from_val(&env, &data).unwrap();
+    assert_eq!(failed.recipient, user1);
+    assert_eq!(failed.reason, PayoutFailureReason::TransferRejected);
+
+    // Enquanto o token continuar rejeitando, o valor segue devido
+    let result = client.try_claim_owed(&quest_id, &user1);
+    assert_eq!(result, Err(Ok(Error::NothingToClaim.into())));
+
+    token_client.block(&user2);
+    client.claim_owed(&quest_id, &user1);
+    assert_eq!(token_client.balance(&user1), 1000);
+    assert!(client.get_owed_rewards(&quest_id, &user1).is_empty());
+}
+
+// Folha da allowlist por Merkle: sha256 do XDR do endereço
+fn merkle_leaf(env: &Env, user: &Address) -> BytesN<32> {
+    env.crypto().sha256(&user.clone().to_xdr(env)).to_bytes()
+}
+
+// Hash de um par de nós, ordenados
+fn merkle_parent(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+    let (first, second) = if a < b { (a, b) } else { (b, a) };
+    let mut data = Bytes::new(env);
+    data.extend_from_array(&first.to_array());
+    data.extend_from_array(&second.to_array());
+    env.crypto().sha256(&data).to_bytes()
+}
+
+#[test]
+fn test_allowlist_explicit_and_merkle() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let create = |title: &str| client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, title),
+        &String::from_str(&env, "Restricted quest"),
+    );
+
+    // Allowlist explícita
+    let explicit_quest = create("Explicit");
+    client.set_allowlist(&explicit_quest, &soroban_sdk::vec![&env, alice.clone()], &true);
+
+    client.register(&explicit_quest, &alice);
+    let result = client.try_register(&explicit_quest, &bob);
+    assert_eq!(result, Err(Ok(Error::NotAllowlisted.into())));
+
+    // Allowlist por Merkle com alice e bob
+    let merkle_quest = create("Merkle");
+    let alice_leaf = merkle_leaf(&env, &alice);
+    let bob_leaf = merkle_leaf(&env, &bob);
+    client.set_allowlist_root(&merkle_quest, &merkle_parent(&env, &alice_leaf, &bob_leaf));
+    assert_eq!(
+        client.get_allowlist_mode(&merkle_quest),
+        Some(AllowlistMode::Merkle(merkle_parent(&env, &alice_leaf, &bob_leaf)))
+    );
+
+    client.register_with_proof(&merkle_quest, &bob, &soroban_sdk::vec![&env, alice_leaf.clone()]);
+    assert!(client.is_user_registered(&merkle_quest, &bob));
+
+    let result = client.try_register_with_proof(&merkle_quest, &carol, &soroban_sdk::vec![&env, alice_leaf]);
+    assert_eq!(result, Err(Ok(Error::NotAllowlisted.into())));
+    assert!(!client.is_allowlisted(&merkle_quest, &carol));
+}
+
+#[test]
+fn test_denylist_blocks_registration_and_payouts() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let owner = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let compliance = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bot = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let contract_id = deploy_quest_manager(&env, &owner);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    client.initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &0u32, &FeeBase::RewardPool);
+    client.set_compliance(&compliance);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &2000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+
+    client.register(&quest_id, &alice);
+    client.mark_user_eligible(&quest_id, &alice);
+
+    client.set_denied(&bot, &true);
+    let result = client.try_register(&quest_id, &bot);
+    assert_eq!(result, Err(Ok(Error::UserDenied.into())));
+
+    // Bloqueado depois de ganhar: o prêmio fica retido como devido
+    client.set_denied(&alice, &true);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&quest_id);
+    client.distribute_rewards(&quest_id);
+
+    let token_client = token::Client::new(&env, &reward_token);
+    assert_eq!(token_client.balance(&alice), 0);
+    assert_eq!(client.get_owed_rewards(&quest_id, &alice).get(0).unwrap().amount, 1000);
+
+    let result = client.try_claim_owed(&quest_id, &alice);
+    assert_eq!(result, Err(Ok(Error::UserDenied.into())));
+
+    client.set_denied(&alice, &false);
+    client.claim_owed(&quest_id, &alice);
+    assert_eq!(token_client.balance(&alice), 1000);
+}
+
+#[test]
+fn test_min_account_age_uses_first_seen() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let owner = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let registrar = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let veteran = Address::generate(&env);
+    let fresh = Address::generate(&env);
+    let attested = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = deploy_quest_manager(&env, &owner);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    client.initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &0u32, &FeeBase::RewardPool);
+    client.set_registrar(&registrar);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000_000);
+    client.check_in(&veteran);
+
+    env.ledger().with_mut(|li| li.timestamp += 7 * 86400);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Test Quest"),
+        &String::from_str(&env, "A test quest"),
+    );
+    client.set_quest_config(&quest_id, &QuestConfig {
+        min_account_age: 86400,
+        ..default_quest_config(&env)
+    });
+
+    client.register(&quest_id, &veteran);
+
+    // Uma carteira nunca vista não passa, nem fica registrada pela tentativa
+    let result = client.try_register(&quest_id, &fresh);
+    assert_eq!(result, Err(Ok(Error::AccountTooNew.into())));
+    assert_eq!(client.get_first_seen(&fresh), None);
+
+    // O registrar pode atestar uma conta mais antiga do que a primeira interação
+    client.check_in(&attested);
+    client.attest_first_seen(&attested, &1_000_000u64);
+    assert_eq!(client.get_first_seen(&attested), Some(1_000_000));
+    client.register(&quest_id, &attested);
+}
+
+#[test]
+fn test_prerequisites_and_quest_chain() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 3000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let create = |title: &str| client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, title),
+        &String::from_str(&env, "Onboarding step"),
+    );
+    let step1 = create("Step 1");
+    let step2 = create("Step 2");
+    let step3 = create("Step 3");
+
+    client.set_prerequisites(&step2, &soroban_sdk::vec![&env,
+        Prerequisite { quest_id: step1, level: PrerequisiteLevel::Eligible },
+    ]);
+    client.set_prerequisites(&step3, &soroban_sdk::vec![&env,
+        Prerequisite { quest_id: step1, level: PrerequisiteLevel::Registered },
+        Prerequisite { quest_id: step2, level: PrerequisiteLevel::Registered },
+    ]);
+
+    // Uma quest só pode depender de quests anteriores
+    let result = client.try_set_prerequisites(&step1, &soroban_sdk::vec![&env,
+        Prerequisite { quest_id: step3, level: PrerequisiteLevel::Registered },
+    ]);
+    assert_eq!(result, Err(Ok(Error::InvalidPrerequisite.into())));
+
+    assert_eq!(client.get_quest_chain(&step3), soroban_sdk::vec![&env, step1, step2, step3]);
+    assert_eq!(client.get_dependents(&step1), soroban_sdk::vec![&env, step2, step3]);
+
+    client.register(&step1, &user);
+    let result = client.try_register(&step2, &user);
+    assert_eq!(result, Err(Ok(Error::PrerequisiteNotMet.into())));
+
+    client.mark_user_eligible(&step1, &user);
+    assert!(client.has_met_prerequisites(&step2, &user));
+    client.register(&step2, &user);
+    client.register(&step3, &user);
+}
+
+#[test]
+fn test_composite_quest_any_two_of_three() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let tasks = soroban_sdk::vec![&env,
+        QuestType::TokenHold(reward_token.clone(), 100u128),
+        QuestType::TradeVolume(10000u128),
+        QuestType::PoolPosition(500u128),
+    ];
+
+    let result = client.try_create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::Composite(tasks.clone(), 4u32),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Combo Quest"),
+        &String::from_str(&env, "Any 2 of 3"),
+    );
+    assert_eq!(result, Err(Ok(Error::InvalidQuestConfig.into())));
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Raffle,
+        &QuestType::Composite(tasks, 2u32),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Combo Quest"),
+        &String::from_str(&env, "Any 2 of 3"),
+    );
+
+    client.register(&quest_id, &user);
+    client.complete_task(&quest_id, &user, &0u32);
+
+    // Uma sub-tarefa não basta
+    assert!(client.get_participants(&quest_id).is_empty());
+    let result = client.try_mark_user_eligible(&quest_id, &user);
+    assert_eq!(result, Err(Ok(Error::TasksIncomplete.into())));
+
+    // Cancelar o registro descarta as sub-tarefas concluídas
+    client.unregister(&quest_id, &user);
+    client.register(&quest_id, &user);
+    assert_eq!(client.get_task_progress(&quest_id, &user), soroban_sdk::vec![&env, false, false, false]);
+
+    client.complete_task(&quest_id, &user, &0u32);
+    client.complete_task(&quest_id, &user, &2u32);
+    assert_eq!(client.get_task_progress(&quest_id, &user), soroban_sdk::vec![&env, true, false, true]);
+    assert_eq!(client.get_participants(&quest_id), soroban_sdk::vec![&env, user.clone()]);
+}
+
+#[test]
+fn test_progress_reaches_target_and_auto_eligibility() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let adapter = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let user = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 1000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, "Volume Quest"),
+        &String::from_str(&env, "Trade 10k"),
+    );
+    client.set_quest_config(&quest_id, &QuestConfig {
+        progress_adapter: Some(adapter.clone()),
+        ..default_quest_config(&env)
+    });
+
+    client.register(&quest_id, &user);
+    client.report_progress(&quest_id, &user, &adapter, &0u32, &5u128);
+
+    // O progresso não sobrevive a um novo registro
+    client.unregister(&quest_id, &user);
+    client.register(&quest_id, &user);
+    assert_eq!(client.get_progress(&quest_id, &user).get(0).unwrap().current, 0);
+
+    client.report_progress(&quest_id, &user, &adapter, &0u32, &4000u128);
+
+    let progress = client.get_progress(&quest_id, &user).get(0).unwrap();
+    assert_eq!(progress, TaskProgress {
+        task: QuestType::TradeVolume(10000u128),
+        current: 4000,
+        target: 10000,
+        completed: false,
+    });
+    assert!(client.get_winners(&quest_id).is_empty());
+
+    let result = client.try_report_progress(&quest_id, &user, &stranger, &0u32, &20000u128);
+    assert_eq!(result, Err(Ok(Error::Unauthorized.into())));
+    let result = client.try_report_progress(&quest_id, &user, &adapter, &1u32, &20000u128);
+    assert_eq!(result, Err(Ok(Error::InvalidTask.into())));
+
+    // Ao atingir a meta o usuário vira ganhador sem chamada extra do backend
+    client.report_progress(&quest_id, &user, &admin, &0u32, &12000u128);
+    assert!(client.get_progress(&quest_id, &user).get(0).unwrap().completed);
+    assert_eq!(client.get_winners(&quest_id), soroban_sdk::vec![&env, user.clone()]);
+}
+
+#[test]
+fn test_referral_bonus_and_referral_quest() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let referrer = Address::generate(&env);
+    let friend = Address::generate(&env);
+    let other_referrer = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+    let token_client = token::Client::new(&env, &reward_token);
+
+    // Pool com 50 de sobra para bônus de indicação
+    let quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &2u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(1000u128),
+        &3600u64,
+        &250u128,
+        &String::from_str(&env, "Swap Quest"),
+        &String::from_str(&env, "Swap once"),
+    );
+    client.set_quest_config(&quest_id, &QuestConfig {
+        referrer_bonus: 50,
+        ..default_quest_config(&env)
+    });
+
+    let referral_quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::Referral(1),
+        &3600u64,
+        &100u128,
+        &String::from_str(&env, "Referral Quest"),
+        &String::from_str(&env, "Invite a friend"),
+    );
+    client.register(&referral_quest_id, &referrer);
+
+    // Leaderboard não aceita elegibilidade automática por indicações
+    let result = client.try_create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &1u32,
+        &DistributionType::Leaderboard,
+        &QuestType::Referral(1),
+        &3600u64,
+        &100u128,
+        &String::from_str(&env, "Referral Race"),
+        &String::from_str(&env, "Invite the most friends"),
+    );
+    assert_eq!(result, Err(Ok(Error::InvalidQuestConfig.into())));
+
+    let result = client.try_register_with_referrer(&quest_id, &friend, &friend);
+    assert_eq!(result, Err(Ok(Error::InvalidReferrer.into())));
+
+    // Uma quest de outro admin não avança a quest de indicação
+    let attacker = Address::generate(&env);
+    let sybil = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &reward_token).mint(&attacker, &100);
+    let throwaway_quest_id = client.create_quest(
+        &attacker,
+        &reward_token,
+        &100u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(1000u128),
+        &3600u64,
+        &100u128,
+        &String::from_str(&env, "Throwaway"),
+        &String::from_str(&env, "Throwaway"),
+    );
+    client.register_with_referrer(&throwaway_quest_id, &sybil, &referrer);
+    client.mark_user_eligible(&throwaway_quest_id, &sybil);
+    assert_eq!(client.get_referral_count(&referrer), 1);
+    assert!(client.get_winners(&referral_quest_id).is_empty());
+
+    client.register_with_referrer(&quest_id, &friend, &referrer);
+    assert_eq!(client.get_referrer(&friend), Some(referrer.clone()));
+
+    // A elegibilidade do indicado paga o bônus e conclui a quest de indicação do mesmo admin
+    client.mark_user_eligible(&quest_id, &friend);
+    assert_eq!(client.get_referral_count(&referrer), 2);
+    assert_eq!(token_client.balance(&referrer), 50);
+    assert_eq!(client.get_quest(&quest_id).total_reward_pool, 200);
+    assert_eq!(client.get_winners(&referral_quest_id), soroban_sdk::vec![&env, referrer.clone()]);
+
+    // O indicador original é mantido e a indicação só é creditada uma vez
+    let second_quest_id = client.create_quest(
+        &admin,
+        &reward_token,
+        &100u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::PoolPosition(1000u128),
+        &3600u64,
+        &100u128,
+        &String::from_str(&env, "Lend Quest"),
+        &String::from_str(&env, "Lend once"),
+    );
+    client.register_with_referrer(&second_quest_id, &friend, &other_referrer);
+    client.mark_user_eligible(&second_quest_id, &friend);
+    assert_eq!(client.get_referrer(&friend), Some(referrer.clone()));
+    assert_eq!(client.get_referral_count(&referrer), 2);
+    assert_eq!(client.get_referral_count(&other_referrer), 0);
+}
+
+#[test]
+fn test_badges_minted_and_used_as_prerequisite() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 3000);
+    let contract_id = env.register_contract(None, QuestManager);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+
+    let create = |title: &str, max_winners: u32| client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &max_winners,
+        &DistributionType::Raffle,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, title),
+        &String::from_str(&env, "Badge quest"),
+    );
+    let onboarding = create("Onboarding", 1);
+    let finale = create("Finale", 1);
+    let advanced = create("Advanced", 1);
+    client.set_quest_config(&onboarding, &QuestConfig {
+        badges: soroban_sdk::vec![&env, BadgeKind::Completion],
+        ..default_quest_config(&env)
+    });
+    client.set_quest_config(&finale, &QuestConfig {
+        badges: soroban_sdk::vec![&env, BadgeKind::Winner],
+        ..default_quest_config(&env)
+    });
+    client.set_prerequisites(&advanced, &soroban_sdk::vec![&env,
+        Prerequisite { quest_id: onboarding, level: PrerequisiteLevel::Badge },
+    ]);
+
+    // O badge de conclusão sai na elegibilidade e libera a quest seguinte
+    client.register(&onboarding, &user);
+    let result = client.try_register(&advanced, &user);
+    assert_eq!(result, Err(Ok(Error::PrerequisiteNotMet.into())));
+    client.mark_user_eligible(&onboarding, &user);
+    assert_eq!(client.get_badges(&user), soroban_sdk::vec![&env,
+        Badge { quest_id: onboarding, kind: BadgeKind::Completion, awarded_at: 0 },
+    ]);
+    client.register(&advanced, &user);
+
+    // O badge de ganhador só sai na resolução, e apenas para quem foi sorteado
+    client.register(&finale, &user);
+    client.register(&finale, &other);
+    client.mark_user_eligible(&finale, &user);
+    client.mark_user_eligible(&finale, &other);
+    assert_eq!(client.get_badges(&other).len(), 0);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&finale);
+
+    let winner = client.get_winners(&finale).get(0).unwrap();
+    let loser = if winner == user { other.clone() } else { user.clone() };
+    assert!(client.get_badges(&winner).contains(Badge { quest_id: finale, kind: BadgeKind::Winner, awarded_at: 3601 }));
+    assert!(!client.get_badges(&loser).iter().any(|badge| badge.quest_id == finale));
+}
+
+#[test]
+fn test_xp_ledger_stats_and_seasons() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let winner = Address::generate(&env);
+    let runner_up = Address::generate(&env);
+    let reward_token = create_reward_token(&env, &admin, 2000);
+    let contract_id = deploy_quest_manager(&env, &owner);
+
+    let client = QuestManagerClient::new(&env, &contract_id);
+    client.initialize_protocol(&owner, &BytesN::from_array(&env, &[0; 32]), &treasury, &0u32, &FeeBase::RewardPool);
+
+    let create = |title: &str| client.create_quest(
+        &admin,
+        &reward_token,
+        &1000u128,
+        &1u32,
+        &DistributionType::Fcfs,
+        &QuestType::TradeVolume(10000u128),
+        &3600u64,
+        &1000u128,
+        &String::from_str(&env, title),
+        &String::from_str(&env, "XP quest"),
+    );
+    let xp_config = QuestConfig {
+        xp: XpRewards { registration: 10, completion: 20, win: 50 },
+        ..default_quest_config(&env)
+    };
+    let first = create("First");
+
+    // O XP de cada etapa é limitado pelo protocolo
+    let result = client.try_set_quest_config(&first, &QuestConfig {
+        xp: XpRewards { registration: 0, completion: 0, win: MAX_XP_REWARD + 1 },
+        ..default_quest_config(&env)
+    });
+    assert_eq!(result, Err(Ok(Error::InvalidQuestConfig.into())));
+    client.set_quest_config(&first, &xp_config);
+
+    client.register(&first, &winner);
+    client.register(&first, &runner_up);
+    client.mark_user_eligible(&first, &winner);
+
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.resolve_quest(&first);
+
+    assert_eq!(client.get_user_stats(&winner), UserStats {
+        total_participated: 1,
+        total_completed: 1,
+        total_won: 1,
+        total_rewards: 1000,
+        win_rate: 10000,
+        xp: 80,
+    });
+    assert_eq!(client.get_user_stats(&runner_up).xp, 10);
+    assert_eq!(client.get_top_users(&1u32), soroban_sdk::vec![&env,
+        LeaderboardEntry { user: winner.clone(), score: 80 },
+        LeaderboardEntry { user: runner_up.clone(), score: 10 },
+    ]);
+
+    // Uma nova temporada zera o ranking, mas mantém o XP total
+    assert_eq!(client.start_season(), 2);
+    let second = create("Second");
+    client.set_quest_config(&second, &xp_config);
+    client.register(&second, &runner_up);
+
+    assert_eq!(client.get_season_xp(&2u32, &runner_up), 10);
+    assert_eq!(client.get_season_xp(&2u32, &winner), 0);
+    assert_eq!(client.get_top_users(&2u32), soroban_sdk::vec![&env,
+        LeaderboardEntry { user: runner_up.clone(), score: 10 },
+    ]);
+    assert_eq!(client.get_user_stats(&runner_up).xp, 20);
+
+    // Cancelar o registro estorna a participação, e registrar de novo não repete o XP
+    client.unregister(&second, &runner_up);
+    assert_eq!(client.get_user_stats(&runner_up).total_participated, 1);
+    client.register(&second, &runner_up);
+    assert_eq!(client.get_user_stats(&runner_up).total_participated, 2);
+    assert_eq!(client.get_season_xp(&2u32, &runner_up), 10);
+    assert_eq!(client.get_user_stats(&winner).win_rate, 10000);
+}
@@ -1,4 +1,5 @@
-use soroban_sdk::{contracttype, Address, String};
+use soroban_sdk::{contracttype, Address, BytesN, String, Vec};
+use crate::QuestType;
 
 // Estruturas de dados para tipos específicos de quest
 #[contracttype]
@@ -28,9 +29,15 @@ pub struct TokenHoldQuest {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct QuestConfig {
     pub allow_multiple_entries: bool, // Permite múltiplas participações do mesmo usuário
+    pub max_entries_per_user: u32, // Limite de entradas por usuário quando allow_multiple_entries está ativo
+    pub max_participants: u32, // Limite de registrados; os demais vão para a fila de espera (0 = sem limite)
     pub require_kyc: bool, // Requer KYC para participar
     pub min_account_age: u64, // Idade mínima da conta em segundos
     pub geographic_restrictions: String, // Restrições geográficas (JSON string)
+    pub progress_adapter: Option<Address>, // Contrato que também pode reportar progresso, além do admin
+    pub referrer_bonus: u128, // Pago do pool a quem indicou cada usuário que se torna elegível (0 = sem bônus)
+    pub badges: Vec<BadgeKind>, // Conquistas que concedem badge nesta quest (vazio = sem badges)
+    pub xp: XpRewards, // XP creditado em cada etapa da quest
 }
 
 // Estrutura para metadados da quest
@@ -43,3 +50,238 @@ pub struct QuestMetadata {
     pub external_url: String, // URL para mais informações
     pub image_url: String, // URL da imagem da quest
 }
+
+// Campos editáveis de uma quest antes da resolução (None mantém o valor atual)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuestUpdate {
+    pub end_timestamp: Option<u64>, // Novo prazo final (não pode ser reduzido após registros)
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub max_winners: Option<u32>, // Não pode ser reduzido após registros
+    pub reward_per_winner: Option<u128>, // Não pode ser reduzido após registros
+}
+
+// Define o que acontece com o pool quando um patrocinador adiciona fundos
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FundingMode {
+    PoolOnly, // Apenas aumenta o pool
+    IncreaseWinners, // Aumenta max_winners com o que o pool passar a cobrir
+    IncreaseReward, // Aumenta reward_per_winner com o que o pool passar a cobrir
+}
+
+// Contribuição de um patrocinador para o pool de uma quest
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SponsorContribution {
+    pub sponsor: Address,
+    pub amount: u128,
+}
+
+// Token adicional pago a cada ganhador junto com o reward_token principal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BonusReward {
+    pub token: Address,
+    pub amount_per_winner: u128,
+    pub total_pool: u128, // Valor em custódia no contrato para este token
+}
+
+// Faixa da tabela de prêmios: posições até last_rank (inclusive) recebem amount
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrizeTier {
+    pub last_rank: u32, // Última posição da faixa (posições começam em 1)
+    pub amount: u128,
+}
+
+// Prêmio de um ganhador conforme sua posição na ordem FCFS ou de sorteio
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WinnerPrize {
+    pub winner: Address,
+    pub rank: u32,
+    pub amount: u128,
+}
+
+// Quantidade de bilhetes de um participante em um sorteio ponderado
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TicketEntry {
+    pub user: Address,
+    pub tickets: u32,
+}
+
+// XP que a quest concede em cada etapa
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct XpRewards {
+    pub registration: u128,
+    pub completion: u128, // Na primeira elegibilidade
+    pub win: u128, // Por prêmio recebido na resolução
+}
+
+// Posição de um usuário no ranking de uma quest Leaderboard ou de XP da temporada
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LeaderboardEntry {
+    pub user: Address,
+    pub score: u128,
+}
+
+// Cronograma de vesting das recompensas: cliff seguido de liberação linear
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VestingSchedule {
+    pub cliff_seconds: u64, // Nada é liberado antes do cliff
+    pub duration_seconds: u64, // Tempo total até a liberação completa, contado da resolução
+    pub start_timestamp: Option<u64>, // Definido quando a quest é resolvida
+}
+
+// Situação do vesting de um ganhador
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VestingStatus {
+    pub total: u128,
+    pub vested: u128,
+    pub claimed: u128,
+    pub remaining: u128, // Ainda não resgatado (total - claimed)
+}
+
+// Estado do acumulador de uma quest Streaming
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamState {
+    pub start_timestamp: u64,
+    pub last_update: u64,
+    pub acc_reward_per_user: u128, // Recompensa acumulada por usuário elegível, escalada por STREAM_PRECISION
+    pub eligible_count: u32,
+    pub distributed: u128, // Total já atribuído aos usuários
+    pub undistributed: u128, // Emitido enquanto não havia elegíveis
+}
+
+// Posição de um usuário em uma quest Streaming
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamPosition {
+    pub is_eligible: bool,
+    pub acc_snapshot: u128, // Valor do acumulador na última atualização do usuário
+    pub pending: u128, // Acumulado e ainda não resgatado
+    pub claimed: u128,
+}
+
+// Taxa de inscrição cobrada no reward_token da quest e somada ao pool de prêmios
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EntryFee {
+    pub amount: u128,
+    pub admin_cut_bps: u32, // Parte da taxa destinada ao admin, em basis points (100 = 1%)
+    pub mode: FundingMode, // Como a parte que vai para o pool altera a quest
+    pub collected_cut: u128, // Parte do admin em custódia até a resolução
+}
+
+// Base de cálculo da taxa do protocolo
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeeBase {
+    RewardPool, // Cobrada do financiador sobre cada aporte ao pool
+    Payout, // Descontada de cada pagamento aos ganhadores
+}
+
+// Configuração global do protocolo (dono, tesouraria e taxa)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolConfig {
+    pub owner: Address,
+    pub treasury: Address,
+    pub fee_bps: u32, // Em basis points (100 = 1%)
+    pub fee_base: FeeBase,
+}
+
+// Taxa do protocolo vigente quando a quest foi criada
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuestFee {
+    pub fee_bps: u32,
+    pub fee_base: FeeBase,
+}
+
+// Pagamento que não pôde ser entregue ao ganhador e fica devido até ser resgatado
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OwedReward {
+    pub token: Address,
+    pub amount: u128,
+}
+
+// Motivo pelo qual um pagamento não pôde ser entregue
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PayoutFailureReason {
+    NoTrustline, // Conta inexistente ou sem trustline para o ativo
+    Unauthorized, // Destinatário desautorizado ou congelado pelo emissor
+    TransferRejected, // O contrato do token rejeitou a transferência
+    Denied, // Destinatário está na denylist do protocolo
+}
+
+// Como a allowlist de uma quest é definida
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AllowlistMode {
+    Explicit, // Endereços adicionados um a um pelo admin
+    Merkle(BytesN<32>), // Raiz de uma árvore de Merkle; o usuário apresenta a prova ao se registrar
+}
+
+// Até onde o usuário precisa ter chegado na quest anterior
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PrerequisiteLevel {
+    Registered,
+    Eligible,
+    Winner,
+    Badge, // Possui o badge concedido pela quest anterior
+}
+
+// Quest que precisa ser cumprida antes do registro em outra
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Prerequisite {
+    pub quest_id: u64,
+    pub level: PrerequisiteLevel,
+}
+
+// Progresso de um usuário em uma tarefa da quest
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskProgress {
+    pub task: QuestType,
+    pub current: u128,
+    pub target: u128,
+    pub completed: bool,
+}
+
+// Indicação de um usuário, registrada uma única vez
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Referral {
+    pub referrer: Address,
+    pub credited: bool, // O indicado já se tornou elegível em alguma quest
+}
+
+// Conquista que concede o badge de uma quest
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BadgeKind {
+    Completion, // Ao se tornar elegível
+    Winner, // Ao estar entre os ganhadores na resolução
+}
+
+// Badge intransferível de um usuário; não há função que o mova para outro endereço
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Badge {
+    pub quest_id: u64,
+    pub kind: BadgeKind,
+    pub awarded_at: u64,
+}